@@ -5,7 +5,19 @@ multiversx_sc::derive_imports!();
 
 pub mod types;
 
-use types::{AgentInfo, AgentStatus, EpochState};
+use types::{
+    AgentDashboard, AgentInfo, AgentStatus, AgentTier, AgentTiersChange, ArbiterChange, BillPreview,
+    BillQuote, BillRejection, BillingGuards, BillingGuardsChange, BondMultiplierScheduleChange,
+    CancelPreview, ChallengeStatus, CreditLimitBand, CreditLimitCurveChange, EnforceableEpoch,
+    EpochMetrics, EpochState, EscrowConfig, FeeCollection, GraceBonusScheduleChange,
+    HardMaxWindowsChange, HealthReport, InsuranceClaim, InsuranceClaimStatus, InsuranceConfigChange,
+    InsuranceSettings, LateFeeRateChange, LateFeeRatePeriod, LeaderboardEntry,
+    MaxBackbillEpochsChange, OperatorChange, OperatorCommissionChange, OwnerChange, PaymentPlan,
+    PaymentPlanStatus, PortfolioHealth, PriceBreakpoint, PriceCurveChange, PromoSlotsChange,
+    RevenueCategory, RevenueShare, RevenueSplitChange, ScoreBand, ServiceChallenge, ServiceCredit,
+    ServiceStats, SolvencyCheck, StreakRebateChange, StreakRebateSettings, TopAgent,
+    WindowRewardChange, find_score_band,
+};
 
 const BPS_DENOMINATOR: u64 = 10_000;
 
@@ -16,12 +28,22 @@ const PROBATION_ON_TIME_EPOCHS_REQUIRED: u64 = 3;
 const PROBATION_MAX_WINDOWS_PER_EPOCH: u64 = 12;
 const EARLY_EXIT_PENALTY_EPOCHS: u64 = 14;
 const EARLY_EXIT_PENALTY_BPS: u64 = 500;
+const MAX_REVENUE_BENEFICIARIES: usize = 10;
+const MAX_METRICS_EPOCHS: u64 = 100;
+const LEADERBOARD_SIZE: usize = 20;
+const MAX_AGENTS_PER_PAGE: usize = 100;
+const MAX_GRACE_BONUS_EPOCHS: u64 = 30;
+const MAX_PRICE_MULTIPLIER_BPS: u64 = 10 * BPS_DENOMINATOR;
 
 const SCORE_BONUS_ON_TIME: u64 = 5;
+const SCORE_BONUS_INSTALLMENT_ON_TIME: u64 = 10;
 const SCORE_PENALTY_LATE: u64 = 15;
 const SCORE_PENALTY_SLASHED: u64 = 60;
 const SCORE_PENALTY_DELINQUENT: u64 = 90;
 
+const ESCROW_CONFIG_SCHEMA_VERSION: u32 = 2;
+const SERVICE_STATS_SCHEMA_VERSION: u32 = 1;
+
 const STATUS_ACTIVE: u64 = 1;
 const STATUS_PAUSED: u64 = 2;
 const STATUS_SUSPENDED: u64 = 3;
@@ -69,11 +91,24 @@ pub trait StreamAgencyEscrow {
         self.total_billed_windows().set(0u64);
         self.total_fees_billed().set(BigUint::zero());
         self.total_protected_value().set(BigUint::zero());
+        self.total_written_off().set(BigUint::zero());
         self.claimable_owner().set(BigUint::zero());
+        self.total_bonds().set(BigUint::zero());
+        self.config_version().set(0u64);
     }
 
     #[upgrade]
-    fn upgrade(&self) {}
+    fn upgrade(&self) {
+        // Deployments from before bonds were tracked in aggregate hold every unit
+        // that is not owed out as claimable or insurance as an agent bond.
+        if self.total_bonds().is_empty() {
+            let balance = self.get_egld_balance();
+            let owed_out = self.total_claimable() + self.insurance_pool().get();
+            if balance > owed_out {
+                self.total_bonds().set(balance - owed_out);
+            }
+        }
+    }
 
     #[endpoint(register)]
     #[payable("EGLD")]
@@ -116,11 +151,11 @@ pub trait StreamAgencyEscrow {
             require!(payment >= required_total, "Insufficient register payment");
 
             if charged_setup_fee > 0u64 {
-                self.claimable_owner().update(|v| *v += &charged_setup_fee);
+                self.credit_revenue(RevenueCategory::SetupFee, &charged_setup_fee);
             }
 
             let bond_add = payment - &charged_setup_fee;
-            self.bond_balance(&caller).set(&bond_add);
+            self.increase_bond(&caller, &bond_add);
             self.outstanding_total(&caller).set(BigUint::zero());
 
             let info = AgentInfo {
@@ -137,24 +172,31 @@ pub trait StreamAgencyEscrow {
             self.agent_info(&caller).set(&info);
             self.agent_probation_on_time(&caller).set(0u64);
             self.agent_probation_graduated(&caller).set(false);
+            self.refresh_agent_tier(&caller);
+            self.update_leaderboard(&caller, DEFAULT_INITIAL_CREDIT);
             self.total_registered_agents()
                 .update(|count| *count += 1u64);
+            self.status_count(&AgentStatus::Suspended)
+                .update(|count| *count += 1u64);
+            self.agents().push(&caller);
 
             self.registered_event(&caller, fee_bps, max_windows_per_epoch, &bond_add);
             return;
         }
 
         // Existing agent update/reactivation.
-        let mut info = self.agent_info(&caller).get();
-        let mut was_active = info.status == AgentStatus::Active;
-
-        if info.status == AgentStatus::Cancelled {
+        let reactivating = self.agent_info(&caller).get().status == AgentStatus::Cancelled;
+        if reactivating {
             require!(self.outstanding_total(&caller).get() == 0u64, "Outstanding debt exists");
-            require!(payment >= self.min_bond().get(), "Need min bond to reactivate");
-            info.status = AgentStatus::Suspended;
+            require!(payment >= self.required_bond(&caller), "Need min bond to reactivate");
+            self.set_status(&caller, AgentStatus::Suspended);
+        }
+
+        let mut info = self.agent_info(&caller).get();
+        let was_active = info.status == AgentStatus::Active;
+        if reactivating {
             info.joined_epoch = now_epoch;
             info.last_billed_epoch = now_epoch.saturating_sub(1);
-            was_active = false;
         }
 
         info.fee_bps = fee_bps;
@@ -163,7 +205,7 @@ pub trait StreamAgencyEscrow {
         info.metadata = metadata;
         self.agent_info(&caller).set(&info);
 
-        self.bond_balance(&caller).update(|v| *v += &payment);
+        self.increase_bond(&caller, &payment);
 
         if !was_active && self.can_be_active(&caller) {
             self.set_status(&caller, AgentStatus::Active);
@@ -184,7 +226,7 @@ pub trait StreamAgencyEscrow {
         let info = self.agent_info(&caller).get();
         require!(info.status != AgentStatus::Cancelled, "Agent cancelled");
 
-        self.bond_balance(&caller).update(|v| *v += &payment);
+        self.increase_bond(&caller, &payment);
         self.bond_topped_up_event(&caller, &payment);
     }
 
@@ -202,9 +244,27 @@ pub trait StreamAgencyEscrow {
 
         let mut info = self.agent_info(&caller).get();
         require!(info.status != AgentStatus::Cancelled, "Agent cancelled");
+        let old_max_windows_per_epoch = info.max_windows_per_epoch;
+        let old_max_charge_per_epoch = info.max_charge_per_epoch.clone();
         info.max_windows_per_epoch = max_windows_per_epoch;
         info.max_charge_per_epoch = max_charge_per_epoch;
         self.agent_info(&caller).set(&info);
+
+        let version = self.bump_config_version();
+        self.billing_guards_changed_event(
+            &caller,
+            &BillingGuardsChange {
+                old: BillingGuards {
+                    max_windows_per_epoch: old_max_windows_per_epoch,
+                    max_charge_per_epoch: old_max_charge_per_epoch,
+                },
+                new: BillingGuards {
+                    max_windows_per_epoch,
+                    max_charge_per_epoch: info.max_charge_per_epoch,
+                },
+                config_version: version,
+            },
+        );
     }
 
     #[endpoint(pause)]
@@ -238,44 +298,30 @@ pub trait StreamAgencyEscrow {
         let caller = self.blockchain().get_caller();
         self.require_agent_exists(&caller);
 
-        let info = self.agent_info(&caller).get();
-        let first_cancellation = info.status != AgentStatus::Cancelled;
-        if first_cancellation {
+        let preview = self.cancel_preview(&caller);
+        if self.agent_info(&caller).get().status != AgentStatus::Cancelled {
             self.set_status(&caller, AgentStatus::Cancelled);
         }
 
         // First, attempt to satisfy outstanding debt from bond.
-        let debt = self.outstanding_total(&caller).get();
-        if debt > 0u64 {
-            let bond = self.bond_balance(&caller).get();
-            let slash = self.min_biguint(&debt, &bond);
-            if slash > 0u64 {
-                self.bond_balance(&caller).set(&(bond - &slash));
-                self.outstanding_total(&caller).set(&(debt - &slash));
-                self.claimable_owner().update(|v| *v += &slash);
-            }
+        if preview.debt_recovered > 0u64 {
+            self.decrease_bond(&caller, &preview.debt_recovered);
+            self.outstanding_total(&caller)
+                .set(&preview.debt_remaining);
+            self.apply_recovered_debt(&caller, &preview.debt_recovered);
+            self.epoch_collected_by_slash(self.blockchain().get_block_epoch())
+                .update(|v| *v += &preview.debt_recovered);
         }
 
         // If an agent churns quickly, take a penalty from bond to discourage whitewashing.
-        if first_cancellation {
-            let current_epoch = self.blockchain().get_block_epoch();
-            let churn_deadline = info
-                .joined_epoch
-                .saturating_add(EARLY_EXIT_PENALTY_EPOCHS);
-            if current_epoch <= churn_deadline {
-                let bond_after_debt = self.bond_balance(&caller).get();
-                let churn_penalty = self.compute_bps_amount(&bond_after_debt, EARLY_EXIT_PENALTY_BPS);
-                if churn_penalty > 0u64 {
-                    self.bond_balance(&caller)
-                        .set(&(bond_after_debt - &churn_penalty));
-                    self.claimable_owner().update(|v| *v += &churn_penalty);
-                }
-            }
+        if preview.churn_penalty > 0u64 {
+            self.decrease_bond(&caller, &preview.churn_penalty);
+            self.credit_revenue(RevenueCategory::ChurnPenalty, &preview.churn_penalty);
         }
 
         let payout = self.bond_balance(&caller).get();
         if payout > 0u64 {
-            self.bond_balance(&caller).clear();
+            self.decrease_bond(&caller, &payout);
             self.send().direct_egld(&caller, &payout);
         }
 
@@ -285,53 +331,27 @@ pub trait StreamAgencyEscrow {
     #[endpoint(billEpoch)]
     fn bill_epoch(&self, agent: ManagedAddress, epoch: u64, windows: u64) -> BigUint {
         self.only_operator();
-        self.require_agent_exists(&agent);
-
-        require!(windows > 0, "Windows must be positive");
-
-        let current_epoch = self.blockchain().get_block_epoch();
-        require!(epoch < current_epoch, "Epoch not closed yet");
-        require!(
-            current_epoch - epoch <= self.max_backbill_epochs().get(),
-            "Epoch too old to bill"
-        );
-
-        let info = self.agent_info(&agent).get();
-        require!(info.status != AgentStatus::Cancelled, "Agent cancelled");
-        require!(epoch >= info.joined_epoch, "Cannot bill before join epoch");
-        require!(epoch > info.last_billed_epoch, "Epoch already passed in billing order");
-        require!(
-            windows <= info.max_windows_per_epoch,
-            "Exceeds max windows per epoch"
-        );
-        if !self.is_probation_graduated(&agent) {
-            require!(
-                windows <= PROBATION_MAX_WINDOWS_PER_EPOCH,
-                "Exceeds probation windows cap"
-            );
-        }
-        require!(
-            windows <= self.hard_max_windows_per_epoch().get(),
-            "Exceeds global windows hard cap"
-        );
-        require!(
-            self.epoch_state(&agent, epoch).is_empty(),
-            "Epoch already billed"
-        );
 
-        let due = self.compute_fee_amount(windows, info.fee_bps);
-        require!(
-            due <= info.max_charge_per_epoch,
-            "Exceeds agent max charge per epoch"
-        );
-        let protected_value = self.compute_protected_value(windows);
+        let quote = match self.check_bill(&agent, epoch, windows) {
+            Ok(quote) => quote,
+            Err(rejection) => sc_panic!(rejection.message()),
+        };
+        let due = quote.due;
+        let protected_value = quote.protected_value;
+        let grace_epochs = quote.grace_epochs;
 
         self.epoch_windows(&agent, epoch).set(windows);
+        self.epoch_protected_value(&agent, epoch).set(&protected_value);
+        self.epoch_billed_fee(&agent, epoch).set(&due);
+        self.epoch_operator(&agent, epoch)
+            .set(self.blockchain().get_caller());
+        self.operator_billed_windows(&self.blockchain().get_caller())
+            .update(|value| *value += windows);
         self.epoch_due(&agent, epoch).set(&due);
-        self.epoch_deadline(&agent, epoch)
-            .set(epoch + self.grace_epochs().get());
+        self.epoch_deadline(&agent, epoch).set(quote.deadline);
         self.epoch_state(&agent, epoch).set(EpochState::Billed);
         self.epoch_score_applied(&agent, epoch).set(false);
+        self.open_epochs(&agent).insert(epoch);
 
         self.outstanding_total(&agent).update(|v| *v += &due);
         self.agent_total_billed_windows(&agent)
@@ -344,14 +364,16 @@ pub trait StreamAgencyEscrow {
             .update(|value| *value += &protected_value);
         self.total_protected_value()
             .update(|value| *value += &protected_value);
+        self.epoch_windows_billed(epoch).update(|value| *value += windows);
+        self.epoch_fees_billed(epoch).update(|value| *value += &due);
 
-        let mut info_mut = info;
+        let mut info_mut = self.agent_info(&agent).get();
         if epoch > info_mut.last_billed_epoch {
             info_mut.last_billed_epoch = epoch;
             self.agent_info(&agent).set(&info_mut);
         }
 
-        self.epoch_billed_event(&agent, epoch, windows, &due);
+        self.epoch_billed_event(&agent, epoch, windows, grace_epochs, &due);
         due
     }
 
@@ -363,6 +385,7 @@ pub trait StreamAgencyEscrow {
 
         require!(!self.epoch_due(&caller, epoch).is_empty(), "Epoch not billed");
 
+        self.accrue_late_fee(&caller, epoch);
         let due = self.epoch_due(&caller, epoch).get();
         require!(due > 0u64, "Epoch already settled");
 
@@ -374,28 +397,31 @@ pub trait StreamAgencyEscrow {
 
         self.epoch_due(&caller, epoch).set(&remaining);
         self.outstanding_total(&caller).update(|v| *v -= &applied);
-        self.claimable_owner().update(|v| *v += &applied);
+        self.collect_epoch_payment(&caller, epoch, RevenueCategory::Settlement, &applied);
+        self.pay_owed_service_credit(&caller);
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        if current_epoch <= self.epoch_deadline(&caller, epoch).get() {
+            self.epoch_collected_on_time(current_epoch)
+                .update(|v| *v += &applied);
+        } else {
+            self.epoch_collected_late(current_epoch)
+                .update(|v| *v += &applied);
+        }
 
         let extra = payment - &applied;
         if extra > 0u64 {
-            self.bond_balance(&caller).update(|v| *v += &extra);
+            self.increase_bond(&caller, &extra);
+        }
+
+        if !self.epoch_score_applied(&caller, epoch).get() {
+            self.agent_streak_fees(&caller)
+                .update(|v| *v += &applied);
         }
 
         if remaining == 0u64 {
-            let current_epoch = self.blockchain().get_block_epoch();
-            let deadline = self.epoch_deadline(&caller, epoch).get();
-            if !self.epoch_score_applied(&caller, epoch).get() {
-                if current_epoch <= deadline {
-                    self.apply_credit_delta(&caller, SCORE_BONUS_ON_TIME as i64);
-                    self.record_probation_outcome(&caller, true);
-                    self.epoch_state(&caller, epoch).set(EpochState::SettledOnTime);
-                } else {
-                    self.apply_credit_delta(&caller, -(SCORE_PENALTY_LATE as i64));
-                    self.record_probation_outcome(&caller, false);
-                    self.epoch_state(&caller, epoch).set(EpochState::SettledLate);
-                }
-                self.epoch_score_applied(&caller, epoch).set(true);
-            }
+            self.open_epochs(&caller).swap_remove(&epoch);
+            self.record_settled_outcome(&caller, epoch, current_epoch);
         }
 
         if self.agent_info(&caller).get().status != AgentStatus::Cancelled && self.can_be_active(&caller) {
@@ -414,6 +440,7 @@ pub trait StreamAgencyEscrow {
         let deadline = self.epoch_deadline(&agent, epoch).get();
         require!(current_epoch > deadline, "Still in grace period");
 
+        self.accrue_late_fee(&agent, epoch);
         let due = self.epoch_due(&agent, epoch).get();
         require!(due > 0u64, "Nothing due");
 
@@ -421,22 +448,29 @@ pub trait StreamAgencyEscrow {
         let slash = self.min_biguint(&due, &bond);
 
         if slash > 0u64 {
-            self.bond_balance(&agent).set(&(bond - &slash));
+            self.decrease_bond(&agent, &slash);
             self.epoch_due(&agent, epoch).set(&(due - &slash));
             self.outstanding_total(&agent).update(|v| *v -= &slash);
-            self.claimable_owner().update(|v| *v += &slash);
+            self.collect_epoch_payment(&agent, epoch, RevenueCategory::Slash, &slash);
+            self.epoch_collected_by_slash(current_epoch)
+                .update(|v| *v += &slash);
         }
 
         let remaining_after = self.epoch_due(&agent, epoch).get();
+        if remaining_after == 0u64 {
+            self.open_epochs(&agent).swap_remove(&epoch);
+        }
 
         if !self.epoch_score_applied(&agent, epoch).get() {
             if remaining_after == 0u64 {
                 self.apply_credit_delta(&agent, -(SCORE_PENALTY_SLASHED as i64));
                 self.record_probation_outcome(&agent, false);
+                self.record_streak_outcome(&agent, false);
                 self.epoch_state(&agent, epoch).set(EpochState::Slashed);
             } else {
                 self.apply_credit_delta(&agent, -(SCORE_PENALTY_DELINQUENT as i64));
                 self.record_probation_outcome(&agent, false);
+                self.record_streak_outcome(&agent, false);
                 self.epoch_state(&agent, epoch).set(EpochState::Delinquent);
             }
             self.epoch_score_applied(&agent, epoch).set(true);
@@ -449,6 +483,373 @@ pub trait StreamAgencyEscrow {
         self.epoch_enforced_event(&agent, epoch, &slash);
     }
 
+    #[endpoint(offerPaymentPlan)]
+    fn offer_payment_plan(
+        &self,
+        agent: ManagedAddress,
+        installments: u64,
+        interval_epochs: u64,
+        max_windows_per_epoch: u64,
+    ) {
+        self.only_owner_or_operator();
+        self.require_agent_exists(&agent);
+
+        require!(installments > 0, "Invalid installment count");
+        require!(interval_epochs > 0, "Invalid installment interval");
+        require!(max_windows_per_epoch > 0, "Invalid max windows");
+        require!(
+            max_windows_per_epoch <= self.hard_max_windows_per_epoch().get(),
+            "Max windows exceeds hard cap"
+        );
+
+        let info = self.agent_info(&agent).get();
+        require!(info.status != AgentStatus::Cancelled, "Agent cancelled");
+
+        // Whatever is left of a defaulted plan is rolled into the new one, but never
+        // more than the debt not tied to an open epoch.
+        let mut total_debt = BigUint::zero();
+        let mut late_fee_debt = BigUint::zero();
+        if !self.payment_plan(&agent).is_empty() {
+            let previous = self.payment_plan(&agent).get();
+            require!(
+                previous.status != PaymentPlanStatus::Current,
+                "Payment plan already active"
+            );
+            if previous.status == PaymentPlanStatus::Defaulted {
+                let mut open_epoch_debt = BigUint::zero();
+                for epoch in self.open_epochs(&agent).iter() {
+                    open_epoch_debt += self.epoch_due(&agent, epoch).get();
+                }
+                let outstanding = self.outstanding_total(&agent).get();
+                let plan_debt = if outstanding > open_epoch_debt {
+                    outstanding - open_epoch_debt
+                } else {
+                    BigUint::zero()
+                };
+                total_debt += self.min_biguint(&previous.remaining(), &plan_debt);
+                late_fee_debt +=
+                    self.min_biguint(&self.payment_plan_late_fee(&agent).get(), &total_debt);
+            }
+        }
+
+        let mut delinquent_epochs = ManagedVec::<Self::Api, u64>::new();
+        for epoch in self.open_epochs(&agent).iter() {
+            if self.epoch_state(&agent, epoch).get() == EpochState::Delinquent {
+                delinquent_epochs.push(epoch);
+            }
+        }
+        for epoch in delinquent_epochs.iter() {
+            self.accrue_late_fee(&agent, epoch);
+            let due = self.epoch_due(&agent, epoch).get();
+            let late_fee_due = self.epoch_late_fee_due(&agent, epoch).get();
+            self.epoch_plan_principal(&agent, epoch)
+                .update(|v| *v += &due - &late_fee_due);
+            total_debt += due;
+            late_fee_debt += late_fee_due;
+            self.epoch_due(&agent, epoch).set(BigUint::zero());
+            self.epoch_late_fee_due(&agent, epoch).clear();
+            self.open_epochs(&agent).swap_remove(&epoch);
+        }
+        require!(total_debt > 0u64, "No delinquent debt");
+
+        let installment_amount = &total_debt / installments;
+        require!(installment_amount > 0u64, "Installment rounds to zero");
+
+        let plan = PaymentPlan {
+            total_debt,
+            paid: BigUint::zero(),
+            installment_amount,
+            installments_total: installments,
+            installments_paid: 0u64,
+            interval_epochs,
+            next_due_epoch: self.blockchain().get_block_epoch() + interval_epochs,
+            max_windows_per_epoch,
+            status: PaymentPlanStatus::Current,
+        };
+        self.payment_plan(&agent).set(&plan);
+        self.payment_plan_late_fee(&agent).set(&late_fee_debt);
+
+        self.payment_plan_offered_event(&agent, installments, interval_epochs, &plan.total_debt);
+    }
+
+    #[endpoint(payInstallment)]
+    #[payable("EGLD")]
+    fn pay_installment(&self) {
+        let caller = self.blockchain().get_caller();
+        self.require_agent_exists(&caller);
+        require!(!self.payment_plan(&caller).is_empty(), "No payment plan");
+
+        let mut plan = self.payment_plan(&caller).get();
+        require!(
+            plan.status == PaymentPlanStatus::Current || plan.status == PaymentPlanStatus::Defaulted,
+            "Payment plan closed"
+        );
+
+        let payment = self.call_value().egld_value().clone_value();
+        require!(payment > 0u64, "Payment required");
+
+        let installment_due = self.min_biguint(
+            &plan.next_installment_due(),
+            &self.outstanding_total(&caller).get(),
+        );
+        let applied = if plan.status == PaymentPlanStatus::Defaulted {
+            self.min_biguint(&payment, &installment_due)
+        } else {
+            require!(payment >= installment_due, "Payment below installment");
+            installment_due
+        };
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        if plan.status == PaymentPlanStatus::Current {
+            if current_epoch <= plan.next_due_epoch {
+                self.apply_credit_delta(&caller, SCORE_BONUS_INSTALLMENT_ON_TIME as i64);
+            }
+            plan.installments_paid += 1;
+            plan.next_due_epoch += plan.interval_epochs;
+        }
+        plan.paid += &applied;
+        if plan.remaining() == 0u64 {
+            plan.status = PaymentPlanStatus::Completed;
+        }
+        self.payment_plan(&caller).set(&plan);
+
+        self.outstanding_total(&caller).update(|v| *v -= &applied);
+        self.collect_plan_payment(&caller, RevenueCategory::Settlement, &applied);
+        self.epoch_collected_late(current_epoch)
+            .update(|v| *v += &applied);
+
+        let extra = payment - &applied;
+        if extra > 0u64 {
+            self.increase_bond(&caller, &extra);
+        }
+
+        if self.agent_info(&caller).get().status != AgentStatus::Cancelled && self.can_be_active(&caller) {
+            self.set_status(&caller, AgentStatus::Active);
+        }
+
+        self.installment_paid_event(&caller, plan.installments_paid, &applied);
+    }
+
+    #[endpoint(enforceInstallment)]
+    fn enforce_installment(&self, agent: ManagedAddress) {
+        self.require_agent_exists(&agent);
+        require!(!self.payment_plan(&agent).is_empty(), "No payment plan");
+
+        let mut plan = self.payment_plan(&agent).get();
+        require!(plan.status == PaymentPlanStatus::Current, "Payment plan not current");
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        require!(current_epoch > plan.next_due_epoch, "Installment not overdue");
+
+        let installment_due = self.min_biguint(
+            &plan.next_installment_due(),
+            &self.outstanding_total(&agent).get(),
+        );
+        require!(installment_due > 0u64, "Nothing due");
+        let bond = self.bond_balance(&agent).get();
+        let slash = self.min_biguint(&installment_due, &bond);
+
+        if slash > 0u64 {
+            self.decrease_bond(&agent, &slash);
+            self.outstanding_total(&agent).update(|v| *v -= &slash);
+            self.collect_plan_payment(&agent, RevenueCategory::Slash, &slash);
+            self.epoch_collected_by_slash(current_epoch)
+                .update(|v| *v += &slash);
+            plan.paid += &slash;
+        }
+
+        if slash == installment_due {
+            self.apply_credit_delta(&agent, -(SCORE_PENALTY_SLASHED as i64));
+            plan.installments_paid += 1;
+            plan.next_due_epoch += plan.interval_epochs;
+            if plan.remaining() == 0u64 {
+                plan.status = PaymentPlanStatus::Completed;
+            }
+        } else {
+            self.apply_credit_delta(&agent, -(SCORE_PENALTY_DELINQUENT as i64));
+            plan.status = PaymentPlanStatus::Defaulted;
+        }
+        self.record_probation_outcome(&agent, false);
+        self.payment_plan(&agent).set(&plan);
+
+        if self.agent_info(&agent).get().status != AgentStatus::Cancelled
+            && !self.can_be_active(&agent)
+        {
+            self.set_status(&agent, AgentStatus::Suspended);
+        }
+
+        self.installment_enforced_event(&agent, plan.installments_paid, &slash);
+    }
+
+    #[endpoint(writeOffDebt)]
+    fn write_off_debt(&self, agent: ManagedAddress, epoch: OptionalValue<u64>) {
+        self.only_owner();
+        self.require_agent_exists(&agent);
+
+        let written_off = match epoch {
+            OptionalValue::Some(epoch) => {
+                require!(!self.epoch_due(&agent, epoch).is_empty(), "Epoch not billed");
+                self.write_off_epoch(&agent, epoch)
+            }
+            OptionalValue::None => {
+                let mut total = BigUint::zero();
+                let mut epochs = ManagedVec::<Self::Api, u64>::new();
+                for open_epoch in self.open_epochs(&agent).iter() {
+                    epochs.push(open_epoch);
+                }
+                for open_epoch in epochs.iter() {
+                    total += self.write_off_epoch(&agent, open_epoch);
+                }
+
+                if !self.payment_plan(&agent).is_empty() {
+                    let mut plan = self.payment_plan(&agent).get();
+                    if plan.status == PaymentPlanStatus::Current
+                        || plan.status == PaymentPlanStatus::Defaulted
+                    {
+                        plan.status = PaymentPlanStatus::WrittenOff;
+                        self.payment_plan(&agent).set(&plan);
+                        self.payment_plan_late_fee(&agent).clear();
+                    }
+                }
+
+                // Covers plan debt and anything not tied to an open epoch.
+                let rest = self.outstanding_total(&agent).get();
+                if rest > 0u64 {
+                    self.outstanding_total(&agent).set(BigUint::zero());
+                    self.record_written_off(&agent, &rest);
+                    total += rest;
+                }
+                total
+            }
+        };
+        require!(written_off > 0u64, "Nothing to write off");
+
+        self.debt_written_off_event(&agent, &written_off);
+    }
+
+    /// Files a claim against the insurance pool for a billed epoch in which the
+    /// service failed, for up to that epoch's protected value.
+    #[endpoint(fileInsuranceClaim)]
+    fn file_insurance_claim(&self, epoch: u64, amount: BigUint) {
+        let caller = self.blockchain().get_caller();
+        self.require_agent_exists(&caller);
+        require!(!self.epoch_due(&caller, epoch).is_empty(), "Epoch not billed");
+        require!(self.insurance_claim(&caller, epoch).is_empty(), "Claim already filed");
+        require!(amount > 0u64, "Amount must be positive");
+        require!(
+            amount <= self.epoch_protected_value_or_windows(&caller, epoch),
+            "Claim exceeds protected value"
+        );
+
+        let claim = InsuranceClaim {
+            amount,
+            filed_epoch: self.blockchain().get_block_epoch(),
+            status: InsuranceClaimStatus::Pending,
+            paid: BigUint::zero(),
+        };
+        self.insurance_claim(&caller, epoch).set(&claim);
+
+        self.insurance_claim_filed_event(&caller, epoch, &claim.amount);
+    }
+
+    /// Approves or rejects a pending claim. An approved claim is paid out up to the
+    /// pool balance and what is left of the agent's lifetime claim limit; the rest
+    /// stays approved and can be paid later with `payInsuranceClaim`.
+    #[endpoint(resolveInsuranceClaim)]
+    fn resolve_insurance_claim(&self, agent: ManagedAddress, epoch: u64, approve: bool) {
+        self.only_operator_or_arbiter();
+        require!(!self.insurance_claim(&agent, epoch).is_empty(), "No claim filed");
+
+        let mut claim = self.insurance_claim(&agent, epoch).get();
+        require!(claim.status == InsuranceClaimStatus::Pending, "Claim already resolved");
+
+        if approve {
+            claim.status = InsuranceClaimStatus::Approved;
+            self.pay_insurance_claim(&agent, epoch, &mut claim);
+        } else {
+            claim.status = InsuranceClaimStatus::Rejected;
+        }
+        self.insurance_claim(&agent, epoch).set(&claim);
+
+        self.insurance_claim_resolved_event(&agent, epoch, approve, &claim.paid);
+    }
+
+    /// Pays what is left of an approved claim, as far as the pool and the agent's
+    /// lifetime claim limit allow now.
+    #[endpoint(payInsuranceClaim)]
+    fn pay_insurance_claim_endpoint(&self, epoch: u64) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.insurance_claim(&caller, epoch).is_empty(), "No claim filed");
+
+        let mut claim = self.insurance_claim(&caller, epoch).get();
+        require!(claim.status == InsuranceClaimStatus::Approved, "Claim not approved");
+        require!(claim.paid < claim.amount, "Claim fully paid");
+
+        let payout = self.pay_insurance_claim(&caller, epoch, &mut claim);
+        require!(payout > 0u64, "Nothing payable yet");
+        self.insurance_claim(&caller, epoch).set(&claim);
+    }
+
+    /// Operator acknowledges windows of a billed epoch it failed to serve; the agent
+    /// is credited the matching share of the billed fee.
+    #[endpoint(recordMissedWindows)]
+    fn record_missed_windows(&self, agent: ManagedAddress, epoch: u64, missed_windows: u64) {
+        self.only_operator();
+        self.require_agent_exists(&agent);
+        self.apply_service_credit(&agent, epoch, missed_windows);
+    }
+
+    #[endpoint(challengeMissedWindows)]
+    fn challenge_missed_windows(&self, epoch: u64, missed_windows: u64) {
+        let caller = self.blockchain().get_caller();
+        self.require_agent_exists(&caller);
+        require!(!self.epoch_due(&caller, epoch).is_empty(), "Epoch not billed");
+        require!(missed_windows > 0, "Invalid missed windows");
+        require!(
+            self.epoch_state(&caller, epoch).get() != EpochState::WrittenOff,
+            "Epoch written off"
+        );
+        require!(
+            self.epoch_missed_windows(&caller, epoch).get() + missed_windows
+                <= self.epoch_windows(&caller, epoch).get(),
+            "Missed windows exceed billed windows"
+        );
+        if !self.service_challenge(&caller, epoch).is_empty() {
+            require!(
+                self.service_challenge(&caller, epoch).get().status != ChallengeStatus::Pending,
+                "Challenge already pending"
+            );
+        }
+
+        let challenge = ServiceChallenge {
+            missed_windows,
+            filed_epoch: self.blockchain().get_block_epoch(),
+            status: ChallengeStatus::Pending,
+        };
+        self.service_challenge(&caller, epoch).set(&challenge);
+
+        self.challenge_filed_event(&caller, epoch, missed_windows);
+    }
+
+    #[endpoint(resolveChallenge)]
+    fn resolve_challenge(&self, agent: ManagedAddress, epoch: u64, accept: bool) {
+        self.only_operator_or_arbiter();
+        require!(!self.service_challenge(&agent, epoch).is_empty(), "No challenge filed");
+
+        let mut challenge = self.service_challenge(&agent, epoch).get();
+        require!(challenge.status == ChallengeStatus::Pending, "Challenge already resolved");
+
+        if accept {
+            self.apply_service_credit(&agent, epoch, challenge.missed_windows);
+            challenge.status = ChallengeStatus::Accepted;
+        } else {
+            challenge.status = ChallengeStatus::Rejected;
+        }
+        self.service_challenge(&agent, epoch).set(&challenge);
+
+        self.challenge_resolved_event(&agent, epoch, accept, challenge.missed_windows);
+    }
+
     #[endpoint(withdrawOwner)]
     fn withdraw_owner(&self, amount: BigUint, to: ManagedAddress) {
         self.only_owner();
@@ -458,172 +859,1981 @@ pub trait StreamAgencyEscrow {
         let claimable = self.claimable_owner().get();
         require!(claimable >= amount, "Insufficient claimable");
 
-        self.claimable_owner().set(&(claimable - &amount));
+        self.require_solvent_after_payout(&amount);
+        self.debit_claimable(&amount);
         self.send().direct_egld(&to, &amount);
 
         self.owner_withdrawn_event(&to, &amount);
     }
 
-    #[endpoint(setOperator)]
-    fn set_operator(&self, new_operator: ManagedAddress) {
-        self.only_owner();
-        require!(!new_operator.is_zero(), "Invalid operator");
-        self.operator().set(&new_operator);
+    #[endpoint(claim)]
+    fn claim(&self) {
+        let caller = self.blockchain().get_caller();
+        let amount = self.beneficiary_claimable(&caller).get();
+        require!(amount > 0u64, "Nothing to claim");
+
+        self.require_solvent_after_payout(&amount);
+        self.beneficiary_claimable(&caller).clear();
+        self.total_beneficiary_claimable()
+            .update(|v| *v -= &amount);
+        self.send().direct_egld(&caller, &amount);
+
+        self.beneficiary_claimed_event(&caller, &amount);
     }
 
-    #[endpoint(setOwner)]
-    fn set_owner(&self, new_owner: ManagedAddress) {
-        self.only_owner();
-        require!(!new_owner.is_zero(), "Invalid owner");
-        self.owner().set(&new_owner);
+    #[endpoint(claimCommission)]
+    fn claim_commission(&self) {
+        let caller = self.blockchain().get_caller();
+        let amount = self.commission_claimable(&caller).get();
+        require!(amount > 0u64, "Nothing to claim");
+
+        self.require_solvent_after_payout(&amount);
+        self.commission_claimable(&caller).clear();
+        self.total_commission_claimable()
+            .update(|v| *v -= &amount);
+        self.send().direct_egld(&caller, &amount);
+
+        self.commission_claimed_event(&caller, &amount);
     }
 
-    #[endpoint(setWindowReward)]
-    fn set_window_reward(&self, window_reward: BigUint) {
+    #[endpoint(setOperatorCommission)]
+    fn set_operator_commission(&self, commission_bps: u64) {
         self.only_owner();
-        require!(window_reward > 0u64, "Window reward must be positive");
-        self.window_reward().set(&window_reward);
+        require!(commission_bps <= BPS_DENOMINATOR, "Invalid commission bps");
+        let old_commission_bps = self.operator_commission_bps().get();
+        self.operator_commission_bps().set(commission_bps);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.operator_commission_changed_event(
+            &caller,
+            &OperatorCommissionChange {
+                old: old_commission_bps,
+                new: commission_bps,
+                config_version: version,
+            },
+        );
     }
 
-    #[endpoint(setPromoSlots)]
-    fn set_promo_slots(&self, promo_free_slots: u64) {
+    #[endpoint(setLateFeeRate)]
+    fn set_late_fee_rate(&self, late_fee_bps_per_epoch: u64) {
         self.only_owner();
-        self.promo_free_slots().set(promo_free_slots);
+        require!(late_fee_bps_per_epoch <= BPS_DENOMINATOR, "Invalid late fee bps");
+        let old_late_fee_bps_per_epoch = self.late_fee_bps_per_epoch().get();
+        self.late_fee_bps_per_epoch().set(late_fee_bps_per_epoch);
+
+        // Keep the rate history so overdue epochs are charged each rate only for the
+        // epochs it was in force. The rate set before the history existed covers the
+        // time up to the first change.
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut history = self.late_fee_rate_history();
+        if history.is_empty() {
+            history.push(&LateFeeRatePeriod {
+                from_epoch: 0,
+                bps_per_epoch: old_late_fee_bps_per_epoch,
+            });
+        }
+        let period = LateFeeRatePeriod {
+            from_epoch: current_epoch,
+            bps_per_epoch: late_fee_bps_per_epoch,
+        };
+        if history.get(history.len()).from_epoch == current_epoch {
+            history.set(history.len(), &period);
+        } else {
+            history.push(&period);
+        }
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.late_fee_rate_changed_event(
+            &caller,
+            &LateFeeRateChange {
+                old: old_late_fee_bps_per_epoch,
+                new: late_fee_bps_per_epoch,
+                config_version: version,
+            },
+        );
     }
 
-    #[endpoint(setMaxBackbillEpochs)]
-    fn set_max_backbill_epochs(&self, max_backbill_epochs: u64) {
+    #[endpoint(setCreditLimitCurve)]
+    fn set_credit_limit_curve(&self, bands: MultiValueEncoded<MultiValue2<u64, BigUint>>) {
         self.only_owner();
-        require!(max_backbill_epochs > 0u64, "Invalid backbill limit");
-        self.max_backbill_epochs().set(max_backbill_epochs);
+
+        let mut curve = ManagedVec::<Self::Api, CreditLimitBand<Self::Api>>::new();
+        let mut previous_min_score: Option<u64> = None;
+        for entry in bands.into_iter() {
+            let (min_score, limit) = entry.into_tuple();
+            self.require_next_band_score(&mut previous_min_score, min_score);
+            curve.push(CreditLimitBand { min_score, limit });
+        }
+
+        let old_curve = self.credit_limit_curve().get();
+        self.credit_limit_curve().set(&curve);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.credit_limit_curve_changed_event(
+            &caller,
+            &CreditLimitCurveChange {
+                old: old_curve,
+                new: curve,
+                config_version: version,
+            },
+        );
     }
 
-    #[endpoint(setHardMaxWindowsPerEpoch)]
-    fn set_hard_max_windows_per_epoch(&self, hard_max_windows_per_epoch: u64) {
+    #[endpoint(setBondMultiplierSchedule)]
+    fn set_bond_multiplier_schedule(&self, bands: MultiValueEncoded<MultiValue2<u64, u64>>) {
         self.only_owner();
-        require!(hard_max_windows_per_epoch > 0u64, "Invalid window hard cap");
-        self.hard_max_windows_per_epoch()
-            .set(hard_max_windows_per_epoch);
-    }
 
-    #[view(getAgentInfo)]
-    fn get_agent_info_view(&self, agent: ManagedAddress) -> OptionalValue<AgentInfo<Self::Api>> {
-        if self.agent_info(&agent).is_empty() {
-            return OptionalValue::None;
+        let schedule = self.build_score_bands(bands);
+        for band in schedule.iter() {
+            require!(band.value > 0u64, "Multiplier must be positive");
         }
-        OptionalValue::Some(self.agent_info(&agent).get())
-    }
 
-    #[view(getAgentFinancials)]
-    fn get_agent_financials_view(
-        &self,
-        agent: ManagedAddress,
-    ) -> MultiValue2<BigUint, BigUint> {
-        (self.bond_balance(&agent).get(), self.outstanding_total(&agent).get()).into()
-    }
+        let old_schedule = self.bond_multiplier_schedule().get();
+        self.bond_multiplier_schedule().set(&schedule);
 
-    #[view(getEpochDebt)]
-    fn get_epoch_debt_view(&self, agent: ManagedAddress, epoch: u64) -> BigUint {
-        self.epoch_due(&agent, epoch).get()
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.bond_multiplier_schedule_changed_event(
+            &caller,
+            &BondMultiplierScheduleChange {
+                old: old_schedule,
+                new: schedule,
+                config_version: version,
+            },
+        );
     }
 
-    #[view(getEpochState)]
-    fn get_epoch_state_view(&self, agent: ManagedAddress, epoch: u64) -> OptionalValue<EpochState> {
+    #[endpoint(setGraceBonusSchedule)]
+    fn set_grace_bonus_schedule(&self, bands: MultiValueEncoded<MultiValue2<u64, u64>>) {
+        self.only_owner();
+
+        let schedule = self.build_score_bands(bands);
+        for band in schedule.iter() {
+            require!(band.value <= MAX_GRACE_BONUS_EPOCHS, "Grace bonus too large");
+        }
+
+        let old_schedule = self.grace_bonus_schedule().get();
+        self.grace_bonus_schedule().set(&schedule);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.grace_bonus_schedule_changed_event(
+            &caller,
+            &GraceBonusScheduleChange {
+                old: old_schedule,
+                new: schedule,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setAgentTiers)]
+    fn set_agent_tiers(
+        &self,
+        tiers: MultiValueEncoded<MultiValue5<ManagedBuffer, u64, u64, u64, u64>>,
+    ) {
+        self.only_owner();
+
+        let mut table = ManagedVec::<Self::Api, AgentTier<Self::Api>>::new();
+        let mut previous_min_score: Option<u64> = None;
+        for entry in tiers.into_iter() {
+            let (name, min_score, max_windows_per_epoch, fee_discount_bps, grace_bonus_epochs) =
+                entry.into_tuple();
+            require!(!name.is_empty(), "Invalid tier name");
+            self.require_next_band_score(&mut previous_min_score, min_score);
+            require!(max_windows_per_epoch > 0, "Invalid max windows");
+            require!(fee_discount_bps < BPS_DENOMINATOR, "Invalid fee discount bps");
+            require!(grace_bonus_epochs <= MAX_GRACE_BONUS_EPOCHS, "Grace bonus too large");
+            for existing in table.iter() {
+                require!(existing.name != name, "Duplicate tier name");
+            }
+            table.push(AgentTier {
+                name,
+                min_score,
+                max_windows_per_epoch,
+                fee_discount_bps,
+                grace_bonus_epochs,
+            });
+        }
+
+        let old_table = self.agent_tiers().get();
+        self.agent_tiers().set(&table);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.agent_tiers_changed_event(
+            &caller,
+            &AgentTiersChange {
+                old: old_table,
+                new: table,
+                config_version: version,
+            },
+        );
+    }
+
+    /// Reassigns the stored tier of one page of agents to the current table, emitting
+    /// `tierChanged` where it moved, and returns the next cursor (0 after the last page).
+    #[endpoint(refreshAgentTiers)]
+    fn refresh_agent_tiers(&self, cursor: usize, max_agents: usize) -> usize {
+        self.only_owner();
+        let (page, next_cursor) = self.agent_page(cursor, max_agents);
+        for agent in page.iter() {
+            self.refresh_agent_tier(&agent);
+        }
+        next_cursor
+    }
+
+    #[endpoint(setStreakRebate)]
+    fn set_streak_rebate(&self, streak_epochs: u64, rebate_bps: u64, cap_per_epoch: BigUint) {
+        self.only_owner();
+        require!(rebate_bps <= BPS_DENOMINATOR, "Invalid rebate bps");
+        require!(rebate_bps == 0 || streak_epochs > 0, "Invalid streak length");
+
+        let old_streak_epochs = self.streak_rebate_epochs().get();
+        let old_rebate_bps = self.streak_rebate_bps().get();
+        let old_cap_per_epoch = self.streak_rebate_cap_per_epoch().get();
+        self.streak_rebate_epochs().set(streak_epochs);
+        self.streak_rebate_bps().set(rebate_bps);
+        self.streak_rebate_cap_per_epoch().set(&cap_per_epoch);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.streak_rebate_changed_event(
+            &caller,
+            &StreakRebateChange {
+                old: StreakRebateSettings {
+                    streak_epochs: old_streak_epochs,
+                    rebate_bps: old_rebate_bps,
+                    cap_per_epoch: old_cap_per_epoch,
+                },
+                new: StreakRebateSettings {
+                    streak_epochs,
+                    rebate_bps,
+                    cap_per_epoch,
+                },
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setPriceCurve)]
+    fn set_price_curve(&self, breakpoints: MultiValueEncoded<MultiValue2<u64, u64>>) {
+        self.only_owner();
+
+        let mut curve = ManagedVec::<Self::Api, PriceBreakpoint>::new();
+        let mut previous_from_window = 1u64;
+        for entry in breakpoints.into_iter() {
+            let (from_window, multiplier_bps) = entry.into_tuple();
+            require!(from_window > previous_from_window, "Invalid breakpoint order");
+            require!(multiplier_bps > 0u64, "Multiplier must be positive");
+            require!(multiplier_bps <= MAX_PRICE_MULTIPLIER_BPS, "Multiplier too large");
+            previous_from_window = from_window;
+            curve.push(PriceBreakpoint {
+                from_window,
+                multiplier_bps,
+            });
+        }
+
+        let old_curve = self.price_curve().get();
+        self.price_curve().set(&curve);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.price_curve_changed_event(
+            &caller,
+            &PriceCurveChange {
+                old: old_curve,
+                new: curve,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setRevenueSplit)]
+    fn set_revenue_split(&self, shares: MultiValueEncoded<MultiValue2<ManagedAddress, u64>>) {
+        self.only_owner();
+
+        let mut split = ManagedVec::<Self::Api, RevenueShare<Self::Api>>::new();
+        let mut total_bps = 0u64;
+        for entry in shares.into_iter() {
+            let (beneficiary, bps) = entry.into_tuple();
+            require!(!beneficiary.is_zero(), "Invalid beneficiary");
+            require!(bps > 0u64, "Share must be positive");
+            for existing in split.iter() {
+                require!(existing.beneficiary != beneficiary, "Duplicate beneficiary");
+            }
+            total_bps += bps;
+            split.push(RevenueShare { beneficiary, bps });
+        }
+        require!(split.len() <= MAX_REVENUE_BENEFICIARIES, "Too many beneficiaries");
+        require!(
+            split.is_empty() || total_bps == BPS_DENOMINATOR,
+            "Shares must sum to 10000 bps"
+        );
+
+        for share in split.iter() {
+            self.beneficiaries().insert(share.beneficiary.clone());
+        }
+
+        let old_split = self.revenue_split().get();
+        self.revenue_split().set(&split);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.revenue_split_changed_event(
+            &caller,
+            &RevenueSplitChange {
+                old: old_split,
+                new: split,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setInsuranceConfig)]
+    fn set_insurance_config(&self, share_bps: u64, claim_limit: BigUint) {
+        self.only_owner();
+        require!(share_bps <= BPS_DENOMINATOR, "Invalid insurance share bps");
+
+        let old_share_bps = self.insurance_share_bps().get();
+        let old_claim_limit = self.insurance_claim_limit().get();
+        self.insurance_share_bps().set(share_bps);
+        self.insurance_claim_limit().set(&claim_limit);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.insurance_config_changed_event(
+            &caller,
+            &InsuranceConfigChange {
+                old: InsuranceSettings {
+                    share_bps: old_share_bps,
+                    claim_limit: old_claim_limit,
+                },
+                new: InsuranceSettings {
+                    share_bps,
+                    claim_limit,
+                },
+                config_version: version,
+            },
+        );
+    }
+
+    /// Sets who besides the operator may resolve insurance claims; the zero address
+    /// removes the arbiter.
+    #[endpoint(setArbiter)]
+    fn set_arbiter(&self, new_arbiter: ManagedAddress) {
+        self.only_owner();
+        let old_arbiter = self.arbiter_or_zero();
+        if new_arbiter.is_zero() {
+            self.arbiter().clear();
+        } else {
+            self.arbiter().set(&new_arbiter);
+        }
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.arbiter_changed_event(
+            &caller,
+            &ArbiterChange {
+                old: old_arbiter,
+                new: new_arbiter,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(checkSolvency)]
+    fn check_solvency(&self) {
+        let balance = self.get_egld_balance();
+        let liabilities = self.total_liabilities();
+        require!(balance >= liabilities, "Contract insolvent");
+
+        let caller = self.blockchain().get_caller();
+        self.solvency_checked_event(
+            &caller,
+            &SolvencyCheck {
+                balance,
+                liabilities,
+            },
+        );
+    }
+
+    #[endpoint(setOperator)]
+    fn set_operator(&self, new_operator: ManagedAddress) {
+        self.only_owner();
+        require!(!new_operator.is_zero(), "Invalid operator");
+        let old_operator = self.operator().get();
+        self.operator().set(&new_operator);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.operator_changed_event(
+            &caller,
+            &OperatorChange {
+                old: old_operator,
+                new: new_operator,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setOwner)]
+    fn set_owner(&self, new_owner: ManagedAddress) {
+        self.only_owner();
+        require!(!new_owner.is_zero(), "Invalid owner");
+        let old_owner = self.owner().get();
+        self.owner().set(&new_owner);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.owner_changed_event(
+            &caller,
+            &OwnerChange {
+                old: old_owner,
+                new: new_owner,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setWindowReward)]
+    fn set_window_reward(&self, window_reward: BigUint) {
+        self.only_owner();
+        require!(window_reward > 0u64, "Window reward must be positive");
+        let old_window_reward = self.window_reward().get();
+        self.window_reward().set(&window_reward);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.window_reward_changed_event(
+            &caller,
+            &WindowRewardChange {
+                old: old_window_reward,
+                new: window_reward,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setPromoSlots)]
+    fn set_promo_slots(&self, promo_free_slots: u64) {
+        self.only_owner();
+        let old_promo_free_slots = self.promo_free_slots().get();
+        self.promo_free_slots().set(promo_free_slots);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.promo_slots_changed_event(
+            &caller,
+            &PromoSlotsChange {
+                old: old_promo_free_slots,
+                new: promo_free_slots,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setMaxBackbillEpochs)]
+    fn set_max_backbill_epochs(&self, max_backbill_epochs: u64) {
+        self.only_owner();
+        require!(max_backbill_epochs > 0u64, "Invalid backbill limit");
+        let old_max_backbill_epochs = self.max_backbill_epochs().get();
+        self.max_backbill_epochs().set(max_backbill_epochs);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.max_backbill_epochs_changed_event(
+            &caller,
+            &MaxBackbillEpochsChange {
+                old: old_max_backbill_epochs,
+                new: max_backbill_epochs,
+                config_version: version,
+            },
+        );
+    }
+
+    #[endpoint(setHardMaxWindowsPerEpoch)]
+    fn set_hard_max_windows_per_epoch(&self, hard_max_windows_per_epoch: u64) {
+        self.only_owner();
+        require!(hard_max_windows_per_epoch > 0u64, "Invalid window hard cap");
+        let old_hard_max_windows_per_epoch = self.hard_max_windows_per_epoch().get();
+        self.hard_max_windows_per_epoch()
+            .set(hard_max_windows_per_epoch);
+
+        let caller = self.blockchain().get_caller();
+        let version = self.bump_config_version();
+        self.hard_max_windows_changed_event(
+            &caller,
+            &HardMaxWindowsChange {
+                old: old_hard_max_windows_per_epoch,
+                new: hard_max_windows_per_epoch,
+                config_version: version,
+            },
+        );
+    }
+
+    #[view(getAgentInfo)]
+    fn get_agent_info_view(&self, agent: ManagedAddress) -> OptionalValue<AgentInfo<Self::Api>> {
+        if self.agent_info(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.agent_info(&agent).get())
+    }
+
+    #[view(getAgentDashboard)]
+    fn get_agent_dashboard_view(
+        &self,
+        agent: ManagedAddress,
+    ) -> OptionalValue<AgentDashboard<Self::Api>> {
+        if self.agent_info(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+
+        let info = self.agent_info(&agent).get();
+        let churn_penalty_free_from_epoch = info
+            .joined_epoch
+            .saturating_add(EARLY_EXIT_PENALTY_EPOCHS)
+            .saturating_add(1);
+        let next_deadline = self
+            .open_epochs(&agent)
+            .iter()
+            .map(|epoch| self.epoch_deadline(&agent, epoch).get())
+            .min();
+
+        OptionalValue::Some(AgentDashboard {
+            info,
+            tier: self.current_tier(&agent),
+            bond: self.bond_balance(&agent).get(),
+            outstanding: self.outstanding_total(&agent).get(),
+            probation_on_time: self.agent_probation_on_time(&agent).get(),
+            probation_required: PROBATION_ON_TIME_EPOCHS_REQUIRED,
+            probation_graduated: self.is_probation_graduated(&agent),
+            total_billed_windows: self.agent_total_billed_windows(&agent).get(),
+            total_fees_billed: self.agent_total_fees_billed(&agent).get(),
+            total_protected_value: self.agent_total_protected_value(&agent).get(),
+            total_written_off: self.agent_total_written_off(&agent).get(),
+            can_be_active: self.can_be_active(&agent),
+            churn_penalty_free_from_epoch,
+            next_deadline,
+        })
+    }
+
+    #[view(getAgentTier)]
+    fn get_agent_tier_view(&self, agent: ManagedAddress) -> OptionalValue<AgentTier<Self::Api>> {
+        if self.agent_info(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+        self.current_tier(&agent).into()
+    }
+
+    #[view(getAgentTiers)]
+    fn get_agent_tiers_view(&self) -> ManagedVec<AgentTier<Self::Api>> {
+        self.agent_tiers().get()
+    }
+
+    #[view(getStreak)]
+    fn get_streak_view(&self, agent: ManagedAddress) -> MultiValue2<u64, BigUint> {
+        (
+            self.agent_on_time_streak(&agent).get(),
+            self.agent_streak_fees(&agent).get(),
+        )
+            .into()
+    }
+
+    /// Rebate earned by completed streaks but not yet paid out.
+    #[view(getStreakRebateOwed)]
+    fn get_streak_rebate_owed_view(&self, agent: ManagedAddress) -> BigUint {
+        self.agent_rebate_owed(&agent).get()
+    }
+
+    #[view(getStreakRebateConfig)]
+    fn get_streak_rebate_config_view(&self) -> MultiValue4<u64, u64, BigUint, BigUint> {
+        let current_epoch = self.blockchain().get_block_epoch();
+        (
+            self.streak_rebate_epochs().get(),
+            self.streak_rebate_bps().get(),
+            self.streak_rebate_cap_per_epoch().get(),
+            self.rebates_paid_in_epoch(current_epoch).get(),
+        )
+            .into()
+    }
+
+    #[view(getHealthReport)]
+    fn get_health_report_view(
+        &self,
+        agent: ManagedAddress,
+    ) -> OptionalValue<HealthReport<Self::Api>> {
+        if self.agent_info(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.health_report(&agent))
+    }
+
+    #[view(previewCancel)]
+    fn preview_cancel_view(
+        &self,
+        agent: ManagedAddress,
+    ) -> OptionalValue<CancelPreview<Self::Api>> {
+        if self.agent_info(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.cancel_preview(&agent))
+    }
+
+    #[view(getAgentFinancials)]
+    fn get_agent_financials_view(
+        &self,
+        agent: ManagedAddress,
+    ) -> MultiValue2<BigUint, BigUint> {
+        (self.bond_balance(&agent).get(), self.outstanding_total(&agent).get()).into()
+    }
+
+    /// Highest-scoring agents, best first; at most `LEADERBOARD_SIZE` entries.
+    #[view(getTopAgents)]
+    fn get_top_agents_view(&self, n: usize) -> MultiValueEncoded<TopAgent<Self::Api>> {
+        let mut result = MultiValueEncoded::new();
+        for entry in self.leaderboard().get().iter().take(n) {
+            let info = self.agent_info(&entry.agent).get();
+            result.push(TopAgent {
+                total_billed_windows: self.agent_total_billed_windows(&entry.agent).get(),
+                agent: entry.agent,
+                credit_score: entry.credit_score,
+                metadata: info.metadata,
+            });
+        }
+        result
+    }
+
+    #[view(getCreditLimit)]
+    fn get_credit_limit_view(&self, agent: ManagedAddress) -> BigUint {
+        if self.agent_info(&agent).is_empty() {
+            return BigUint::zero();
+        }
+        self.credit_limit(&agent)
+    }
+
+    #[view(getCreditLimitCurve)]
+    fn get_credit_limit_curve_view(&self) -> MultiValueEncoded<MultiValue2<u64, BigUint>> {
+        let mut result = MultiValueEncoded::new();
+        for band in self.credit_limit_curve().get().iter() {
+            result.push((band.min_score, band.limit.clone()).into());
+        }
+        result
+    }
+
+    #[view(getRequiredBond)]
+    fn get_required_bond_view(&self, agent: ManagedAddress) -> BigUint {
+        if self.agent_info(&agent).is_empty() {
+            return self.min_bond().get();
+        }
+        self.required_bond(&agent)
+    }
+
+    #[view(getBondMultiplierSchedule)]
+    fn get_bond_multiplier_schedule_view(&self) -> MultiValueEncoded<MultiValue2<u64, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for band in self.bond_multiplier_schedule().get().iter() {
+            result.push((band.min_score, band.value).into());
+        }
+        result
+    }
+
+    #[view(getGraceEpochs)]
+    fn get_grace_epochs_view(&self, agent: ManagedAddress) -> u64 {
+        if self.agent_info(&agent).is_empty() {
+            return self.grace_epochs().get();
+        }
+        self.effective_grace_epochs(&agent)
+    }
+
+    #[view(getGraceBonusSchedule)]
+    fn get_grace_bonus_schedule_view(&self) -> MultiValueEncoded<MultiValue2<u64, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for band in self.grace_bonus_schedule().get().iter() {
+            result.push((band.min_score, band.value).into());
+        }
+        result
+    }
+
+    #[view(quoteFee)]
+    fn quote_fee_view(&self, agent: ManagedAddress, windows: u64) -> BigUint {
+        if self.agent_info(&agent).is_empty() || windows == 0 {
+            return BigUint::zero();
+        }
+        let info = self.agent_info(&agent).get();
+        self.compute_agent_due(windows, info.fee_bps, &self.current_tier(&agent))
+    }
+
+    /// Runs every `billEpoch` guard without writing state.
+    #[view(previewBill)]
+    fn preview_bill_view(
+        &self,
+        agent: ManagedAddress,
+        epoch: u64,
+        windows: u64,
+    ) -> BillPreview<Self::Api> {
+        match self.check_bill(&agent, epoch, windows) {
+            Ok(quote) => BillPreview::Accepted(quote),
+            Err(rejection) => BillPreview::Rejected(rejection),
+        }
+    }
+
+    #[view(getPriceCurve)]
+    fn get_price_curve_view(&self) -> MultiValueEncoded<MultiValue2<u64, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for breakpoint in self.price_curve().get().iter() {
+            result.push((breakpoint.from_window, breakpoint.multiplier_bps).into());
+        }
+        result
+    }
+
+    /// Includes late fees that would be accrued if the epoch were touched now.
+    #[view(getEpochDebt)]
+    fn get_epoch_debt_view(&self, agent: ManagedAddress, epoch: u64) -> BigUint {
+        self.epoch_due(&agent, epoch).get() + self.pending_late_fee(&agent, epoch).0
+    }
+
+    #[view(getEpochLateFee)]
+    fn get_epoch_late_fee_view(&self, agent: ManagedAddress, epoch: u64) -> BigUint {
+        self.epoch_late_fee_due(&agent, epoch).get() + self.pending_late_fee(&agent, epoch).0
+    }
+
+    #[view(getPaymentPlan)]
+    fn get_payment_plan_view(&self, agent: ManagedAddress) -> OptionalValue<PaymentPlan<Self::Api>> {
+        if self.payment_plan(&agent).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.payment_plan(&agent).get())
+    }
+
+    #[view(getEpochState)]
+    fn get_epoch_state_view(&self, agent: ManagedAddress, epoch: u64) -> OptionalValue<EpochState> {
         if self.epoch_state(&agent, epoch).is_empty() {
             return OptionalValue::None;
         }
-        OptionalValue::Some(self.epoch_state(&agent, epoch).get())
+        OptionalValue::Some(self.epoch_state(&agent, epoch).get())
+    }
+
+    /// Billed epochs past their deadline that `enforceEpoch` would accept now, for up to
+    /// `max_agents` agents of the agent list starting at `cursor`. Returns the cursor of
+    /// the next page (0 once the list is done) followed by the epochs found. Only agents
+    /// registered since the agent list was introduced are scanned.
+    #[view(getEnforceable)]
+    fn get_enforceable_view(
+        &self,
+        cursor: usize,
+        max_agents: usize,
+    ) -> MultiValue2<usize, MultiValueEncoded<EnforceableEpoch<Self::Api>>> {
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut result = MultiValueEncoded::new();
+
+        let (page, next_cursor) = self.agent_page(cursor, max_agents);
+        for agent in page.iter() {
+            // Each enforcement slashes the bond, so later epochs see what is left of it.
+            let mut bond = self.bond_balance(&agent).get();
+            for epoch in self.open_epochs(&agent).iter() {
+                if current_epoch <= self.epoch_deadline(&agent, epoch).get() {
+                    continue;
+                }
+                let due = self.epoch_due(&agent, epoch).get()
+                    + self.pending_late_fee(&agent, epoch).0;
+                if due == 0u64 {
+                    continue;
+                }
+
+                let expected_slash = self.min_biguint(&due, &bond);
+                bond -= &expected_slash;
+                let outcome = if expected_slash == due {
+                    EpochState::Slashed
+                } else {
+                    EpochState::Delinquent
+                };
+                result.push(EnforceableEpoch {
+                    agent: agent.clone_value(),
+                    epoch,
+                    due,
+                    expected_slash,
+                    outcome,
+                });
+            }
+        }
+        (next_cursor, result).into()
+    }
+
+    /// Per-epoch aggregates for `from..=to`. Windows and fees are recorded under the
+    /// billed epoch; collections and the active agent count under the epoch they happen in.
+    #[view(getEpochMetrics)]
+    fn get_epoch_metrics_view(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> MultiValueEncoded<EpochMetrics<Self::Api>> {
+        require!(from <= to, "Invalid epoch range");
+        require!(to - from < MAX_METRICS_EPOCHS, "Epoch range too large");
+
+        let mut result = MultiValueEncoded::new();
+        for epoch in from..=to {
+            result.push(EpochMetrics {
+                epoch,
+                windows_billed: self.epoch_windows_billed(epoch).get(),
+                fees_billed: self.epoch_fees_billed(epoch).get(),
+                collected_on_time: self.epoch_collected_on_time(epoch).get(),
+                collected_late: self.epoch_collected_late(epoch).get(),
+                collected_by_slash: self.epoch_collected_by_slash(epoch).get(),
+                active_agents: self.active_agents_in_epoch(epoch),
+            });
+        }
+        result
+    }
+
+    /// Agents per status (service-wide) and the outstanding debt, aged by epochs past
+    /// deadline, of up to `max_agents` agents of the agent list starting at `cursor`.
+    /// Returns the cursor of the next page (0 once the list is done) first; callers sum
+    /// the debt buckets over all pages.
+    #[view(getPortfolioHealth)]
+    fn get_portfolio_health_view(
+        &self,
+        cursor: usize,
+        max_agents: usize,
+    ) -> MultiValue2<usize, PortfolioHealth<Self::Api>> {
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut health = PortfolioHealth {
+            active_agents: self.active_agent_count().get(),
+            paused_agents: self.status_count(&AgentStatus::Paused).get(),
+            suspended_agents: self.status_count(&AgentStatus::Suspended).get(),
+            cancelled_agents: self.status_count(&AgentStatus::Cancelled).get(),
+            outstanding_current: BigUint::zero(),
+            outstanding_1_to_2_epochs: BigUint::zero(),
+            outstanding_3_to_5_epochs: BigUint::zero(),
+            outstanding_over_5_epochs: BigUint::zero(),
+        };
+
+        let (page, next_cursor) = self.agent_page(cursor, max_agents);
+        for agent in page.iter() {
+            for epoch in self.open_epochs(&agent).iter() {
+                let due = self.epoch_due(&agent, epoch).get()
+                    + self.pending_late_fee(&agent, epoch).0;
+                let deadline = self.epoch_deadline(&agent, epoch).get();
+                self.add_aged_debt(&mut health, current_epoch.saturating_sub(deadline), &due);
+            }
+
+            if !self.payment_plan(&agent).is_empty() {
+                let plan = self.payment_plan(&agent).get();
+                if plan.status == PaymentPlanStatus::Current
+                    || plan.status == PaymentPlanStatus::Defaulted
+                {
+                    let age = current_epoch.saturating_sub(plan.next_due_epoch);
+                    self.add_aged_debt(&mut health, age, &plan.remaining());
+                }
+            }
+        }
+        (next_cursor, health).into()
+    }
+
+    #[view(getClaimableOwner)]
+    fn get_claimable_owner_view(&self) -> BigUint {
+        self.claimable_owner().get()
+    }
+
+    #[view(getRevenueBreakdown)]
+    fn get_revenue_breakdown_view(
+        &self,
+    ) -> MultiValueEncoded<MultiValue3<RevenueCategory, BigUint, BigUint>> {
+        let mut result = MultiValueEncoded::new();
+        for category in RevenueCategory::all() {
+            let claimable = self.claimable_by_category(&category).get();
+            let lifetime = self.lifetime_revenue(&category).get();
+            result.push((category, claimable, lifetime).into());
+        }
+        result
+    }
+
+    #[view(getRevenueSplit)]
+    fn get_revenue_split_view(&self) -> MultiValueEncoded<MultiValue2<ManagedAddress, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for share in self.revenue_split().get().iter() {
+            result.push((share.beneficiary.clone(), share.bps).into());
+        }
+        result
+    }
+
+    #[view(getBeneficiaryBalances)]
+    fn get_beneficiary_balances_view(
+        &self,
+    ) -> MultiValueEncoded<MultiValue3<ManagedAddress, u64, BigUint>> {
+        let split = self.revenue_split().get();
+        let mut result = MultiValueEncoded::new();
+        for beneficiary in self.beneficiaries().iter() {
+            let bps = split
+                .iter()
+                .find(|share| share.beneficiary == beneficiary)
+                .map(|share| share.bps)
+                .unwrap_or(0u64);
+            let claimable = self.beneficiary_claimable(&beneficiary).get();
+            result.push((beneficiary, bps, claimable).into());
+        }
+        result
+    }
+
+    #[view(getOperatorCommission)]
+    fn get_operator_commission_view(
+        &self,
+        operator: ManagedAddress,
+    ) -> MultiValue3<u64, BigUint, BigUint> {
+        (
+            self.operator_commission_bps().get(),
+            self.commission_earned(&operator).get(),
+            self.commission_claimable(&operator).get(),
+        )
+            .into()
+    }
+
+    #[view(getInsurancePool)]
+    fn get_insurance_pool_view(&self) -> MultiValue3<BigUint, BigUint, BigUint> {
+        (
+            self.insurance_pool().get(),
+            self.insurance_funded().get(),
+            self.insurance_paid_out().get(),
+        )
+            .into()
+    }
+
+    #[view(getInsuranceConfig)]
+    fn get_insurance_config_view(&self) -> MultiValue3<u64, BigUint, ManagedAddress> {
+        (
+            self.insurance_share_bps().get(),
+            self.insurance_claim_limit().get(),
+            self.arbiter_or_zero(),
+        )
+            .into()
+    }
+
+    #[view(getInsuranceClaim)]
+    fn get_insurance_claim_view(
+        &self,
+        agent: ManagedAddress,
+        epoch: u64,
+    ) -> OptionalValue<InsuranceClaim<Self::Api>> {
+        if self.insurance_claim(&agent, epoch).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.insurance_claim(&agent, epoch).get())
+    }
+
+    #[view(getAgentInsurancePaid)]
+    fn get_agent_insurance_paid_view(&self, agent: ManagedAddress) -> BigUint {
+        self.agent_insurance_paid(&agent).get()
+    }
+
+    #[view(getServiceCredit)]
+    fn get_service_credit_view(
+        &self,
+        agent: ManagedAddress,
+        epoch: u64,
+    ) -> MultiValue2<u64, BigUint> {
+        (
+            self.epoch_missed_windows(&agent, epoch).get(),
+            self.epoch_service_credit(&agent, epoch).get(),
+        )
+            .into()
+    }
+
+    /// Service credit that could not be refunded yet; paid on the agent's next settlement.
+    #[view(getServiceCreditOwed)]
+    fn get_service_credit_owed_view(&self, agent: ManagedAddress) -> BigUint {
+        self.agent_service_credit_owed(&agent).get()
+    }
+
+    #[view(getServiceChallenge)]
+    fn get_service_challenge_view(
+        &self,
+        agent: ManagedAddress,
+        epoch: u64,
+    ) -> OptionalValue<ServiceChallenge> {
+        if self.service_challenge(&agent, epoch).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.service_challenge(&agent, epoch).get())
+    }
+
+    /// Windows billed and missed by `operator`, credits issued for the misses and
+    /// the share of billed windows actually served, in bps.
+    #[view(getOperatorReliability)]
+    fn get_operator_reliability_view(
+        &self,
+        operator: ManagedAddress,
+    ) -> MultiValue4<u64, u64, BigUint, u64> {
+        let billed = self.operator_billed_windows(&operator).get();
+        let missed = self.operator_missed_windows(&operator).get();
+        let reliability_bps = (billed.saturating_sub(missed) * BPS_DENOMINATOR)
+            .checked_div(billed)
+            .unwrap_or(BPS_DENOMINATOR);
+        (
+            billed,
+            missed,
+            self.operator_service_credits(&operator).get(),
+            reliability_bps,
+        )
+            .into()
+    }
+
+    #[view(getSolvency)]
+    fn get_solvency_view(
+        &self,
+    ) -> MultiValue6<BigUint, BigUint, BigUint, BigUint, BigUint, BigUint> {
+        let balance = self.get_egld_balance();
+        let total_bonds = self.total_bonds().get();
+        let claimable = self.total_claimable();
+        let insurance_pool = self.insurance_pool().get();
+        let liabilities = self.total_liabilities();
+
+        let (surplus, deficit) = if balance >= liabilities {
+            (&balance - &liabilities, BigUint::zero())
+        } else {
+            (BigUint::zero(), &liabilities - &balance)
+        };
+        (balance, total_bonds, claimable, surplus, deficit, insurance_pool).into()
+    }
+
+    #[view(getServiceStats)]
+    fn get_service_stats_view(
+        &self,
+    ) -> MultiValue7<u64, u64, u64, u64, u64, BigUint, BigUint> {
+        (
+            self.total_registered_agents().get(),
+            self.active_agent_count().get(),
+            self.promo_used().get(),
+            self.promo_free_slots().get(),
+            self.total_billed_windows().get(),
+            self.total_fees_billed().get(),
+            self.total_protected_value().get(),
+        )
+            .into()
+    }
+
+    #[view(getAgentServiceStats)]
+    fn get_agent_service_stats_view(
+        &self,
+        agent: ManagedAddress,
+    ) -> MultiValue3<u64, BigUint, BigUint> {
+        if self.agent_info(&agent).is_empty() {
+            return (0u64, BigUint::zero(), BigUint::zero()).into();
+        }
+        (
+            self.agent_total_billed_windows(&agent).get(),
+            self.agent_total_fees_billed(&agent).get(),
+            self.agent_total_protected_value(&agent).get(),
+        )
+            .into()
+    }
+
+    /// Lifetime bad debt: service-wide total and the given agent's share.
+    #[view(getWrittenOff)]
+    fn get_written_off_view(&self, agent: ManagedAddress) -> MultiValue2<BigUint, BigUint> {
+        (
+            self.total_written_off().get(),
+            self.agent_total_written_off(&agent).get(),
+        )
+            .into()
+    }
+
+    #[view(getConfig)]
+    fn get_config_view(
+        &self,
+    ) -> MultiValue10<
+        ManagedAddress,
+        ManagedAddress,
+        BigUint,
+        BigUint,
+        BigUint,
+        u64,
+        u64,
+        u64,
+        u64,
+        u64,
+    > {
+        (
+            self.owner().get(),
+            self.operator().get(),
+            self.window_reward().get(),
+            self.setup_fee().get(),
+            self.min_bond().get(),
+            self.promo_free_slots().get(),
+            self.grace_epochs().get(),
+            self.max_backbill_epochs().get(),
+            self.hard_max_windows_per_epoch().get(),
+            self.config_version().get(),
+        )
+            .into()
+    }
+
+    #[view(getEscrowConfig)]
+    fn get_escrow_config_view(&self) -> EscrowConfig<Self::Api> {
+        EscrowConfig {
+            schema_version: ESCROW_CONFIG_SCHEMA_VERSION,
+            config_version: self.config_version().get(),
+            owner: self.owner().get(),
+            operator: self.operator().get(),
+            window_reward: self.window_reward().get(),
+            setup_fee: self.setup_fee().get(),
+            min_bond: self.min_bond().get(),
+            promo_free_slots: self.promo_free_slots().get(),
+            grace_epochs: self.grace_epochs().get(),
+            max_backbill_epochs: self.max_backbill_epochs().get(),
+            hard_max_windows_per_epoch: self.hard_max_windows_per_epoch().get(),
+            operator_commission_bps: self.operator_commission_bps().get(),
+            late_fee_bps_per_epoch: self.late_fee_bps_per_epoch().get(),
+            streak_rebate_epochs: self.streak_rebate_epochs().get(),
+            streak_rebate_bps: self.streak_rebate_bps().get(),
+            streak_rebate_cap_per_epoch: self.streak_rebate_cap_per_epoch().get(),
+            insurance_share_bps: self.insurance_share_bps().get(),
+            insurance_claim_limit: self.insurance_claim_limit().get(),
+            arbiter: self.arbiter_or_zero(),
+        }
+    }
+
+    #[view(getEscrowStats)]
+    fn get_escrow_stats_view(&self) -> ServiceStats<Self::Api> {
+        ServiceStats {
+            schema_version: SERVICE_STATS_SCHEMA_VERSION,
+            total_registered_agents: self.total_registered_agents().get(),
+            active_agent_count: self.active_agent_count().get(),
+            promo_used: self.promo_used().get(),
+            promo_free_slots: self.promo_free_slots().get(),
+            total_billed_windows: self.total_billed_windows().get(),
+            total_fees_billed: self.total_fees_billed().get(),
+            total_protected_value: self.total_protected_value().get(),
+            total_written_off: self.total_written_off().get(),
+        }
+    }
+
+    #[view(getPromoUsage)]
+    fn get_promo_usage_view(&self) -> MultiValue2<u64, u64> {
+        (self.promo_used().get(), self.promo_free_slots().get()).into()
+    }
+
+    #[view(getActiveAgentCount)]
+    fn get_active_agent_count_view(&self) -> u64 {
+        self.active_agent_count().get()
+    }
+
+    /// What `cancelAndWithdraw` would do right now. The churn penalty only applies on
+    /// the first cancellation, up to `EARLY_EXIT_PENALTY_EPOCHS` after joining.
+    fn cancel_preview(&self, agent: &ManagedAddress) -> CancelPreview<Self::Api> {
+        let info = self.agent_info(agent).get();
+        let bond = self.bond_balance(agent).get();
+        let debt = self.outstanding_total(agent).get();
+        let debt_recovered = self.min_biguint(&debt, &bond);
+        let debt_remaining = &debt - &debt_recovered;
+        let bond_after_debt = &bond - &debt_recovered;
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let churn_deadline = info
+            .joined_epoch
+            .saturating_add(EARLY_EXIT_PENALTY_EPOCHS);
+        let churn_penalty =
+            if info.status != AgentStatus::Cancelled && current_epoch <= churn_deadline {
+                self.compute_bps_amount(&bond_after_debt, EARLY_EXIT_PENALTY_BPS)
+            } else {
+                BigUint::zero()
+            };
+        let payout = &bond_after_debt - &churn_penalty;
+
+        CancelPreview {
+            bond,
+            debt_recovered,
+            debt_remaining,
+            churn_penalty,
+            penalty_free_from_epoch: churn_deadline.saturating_add(1),
+            payout,
+        }
+    }
+
+    fn check_bill(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+        windows: u64,
+    ) -> Result<BillQuote<Self::Api>, BillRejection> {
+        if self.agent_info(agent).is_empty() {
+            return Err(BillRejection::AgentNotEnrolled);
+        }
+        if windows == 0 {
+            return Err(BillRejection::ZeroWindows);
+        }
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        if epoch >= current_epoch {
+            return Err(BillRejection::EpochNotClosed);
+        }
+        if current_epoch - epoch > self.max_backbill_epochs().get() {
+            return Err(BillRejection::EpochTooOld);
+        }
+
+        let info = self.agent_info(agent).get();
+        if info.status == AgentStatus::Cancelled {
+            return Err(BillRejection::AgentCancelled);
+        }
+        if epoch < info.joined_epoch {
+            return Err(BillRejection::BeforeJoinEpoch);
+        }
+        if epoch <= info.last_billed_epoch {
+            return Err(BillRejection::OutOfBillingOrder);
+        }
+        if windows > info.max_windows_per_epoch {
+            return Err(BillRejection::ExceedsAgentWindows);
+        }
+        if !self.is_probation_graduated(agent) && windows > PROBATION_MAX_WINDOWS_PER_EPOCH {
+            return Err(BillRejection::ExceedsProbationWindows);
+        }
+        if windows > self.hard_max_windows_per_epoch().get() {
+            return Err(BillRejection::ExceedsHardCap);
+        }
+        let tier = self.current_tier(agent);
+        if let Some(tier) = &tier {
+            if windows > tier.max_windows_per_epoch {
+                return Err(BillRejection::ExceedsTierWindows);
+            }
+        }
+        if !self.payment_plan(agent).is_empty() {
+            let plan = self.payment_plan(agent).get();
+            if plan.status == PaymentPlanStatus::Current && windows > plan.max_windows_per_epoch {
+                return Err(BillRejection::ExceedsPaymentPlanWindows);
+            }
+        }
+        if !self.epoch_state(agent, epoch).is_empty() {
+            return Err(BillRejection::AlreadyBilled);
+        }
+
+        let due = self.compute_agent_due(windows, info.fee_bps, &tier);
+        if due == 0u64 {
+            return Err(BillRejection::FeeRoundsToZero);
+        }
+        if due > info.max_charge_per_epoch {
+            return Err(BillRejection::ExceedsMaxCharge);
+        }
+        // A limit of 0 means no postpaid debt: the bill is recorded and must be settled
+        // before the agent can stay active.
+        let credit_limit = self.credit_limit(agent);
+        if credit_limit > 0u64 && self.unscheduled_debt(agent) + &due > credit_limit {
+            return Err(BillRejection::ExceedsCreditLimit);
+        }
+
+        let grace_epochs = self.effective_grace_epochs(agent);
+        Ok(BillQuote {
+            due,
+            protected_value: self.compute_protected_value(windows),
+            grace_epochs,
+            deadline: epoch.saturating_add(grace_epochs),
+        })
+    }
+
+    fn compute_fee_amount(&self, windows: u64, fee_bps: u64) -> BigUint {
+        let mut fee = self.window_reward().get();
+        fee *= &self.weighted_windows_bps(windows);
+        fee *= fee_bps;
+        fee /= BPS_DENOMINATOR * BPS_DENOMINATOR;
+        fee
+    }
+
+    /// Sum of per-window price multipliers in bps. Windows before the first
+    /// breakpoint (or all of them, without a curve) count at full price.
+    fn weighted_windows_bps(&self, windows: u64) -> BigUint {
+        let mut weighted = BigUint::zero();
+        let mut segment_start = 1u64;
+        let mut multiplier_bps = BPS_DENOMINATOR;
+        for breakpoint in self.price_curve().get().iter() {
+            if breakpoint.from_window > windows {
+                break;
+            }
+            weighted += BigUint::from(breakpoint.from_window - segment_start) * multiplier_bps;
+            segment_start = breakpoint.from_window;
+            multiplier_bps = breakpoint.multiplier_bps;
+        }
+        weighted + BigUint::from(windows + 1 - segment_start) * multiplier_bps
+    }
+
+    fn compute_agent_due(
+        &self,
+        windows: u64,
+        fee_bps: u64,
+        tier: &Option<AgentTier<Self::Api>>,
+    ) -> BigUint {
+        let mut due = self.compute_fee_amount(windows, fee_bps);
+        if let Some(tier) = tier {
+            due -= self.compute_bps_amount(&due, tier.fee_discount_bps);
+        }
+        due
+    }
+
+    fn compute_protected_value(&self, windows: u64) -> BigUint {
+        let mut value = self.window_reward().get();
+        value *= windows;
+        value
+    }
+
+    fn compute_bps_amount(&self, amount: &BigUint, bps: u64) -> BigUint {
+        if bps == 0 || amount == &BigUint::zero() {
+            return BigUint::zero();
+        }
+        let mut value = amount.clone();
+        value *= bps;
+        value /= BPS_DENOMINATOR;
+        value
+    }
+
+    fn increase_bond(&self, agent: &ManagedAddress, amount: &BigUint) {
+        self.bond_balance(agent).update(|v| *v += amount);
+        self.total_bonds().update(|v| *v += amount);
+    }
+
+    fn decrease_bond(&self, agent: &ManagedAddress, amount: &BigUint) {
+        self.bond_balance(agent).update(|v| *v -= amount);
+        // Bonds posted before the aggregate existed are not counted in it.
+        self.total_bonds().update(|v| {
+            *v = if *v >= *amount {
+                &*v - amount
+            } else {
+                BigUint::zero()
+            }
+        });
+    }
+
+    fn get_egld_balance(&self) -> BigUint {
+        self.blockchain()
+            .get_sc_balance(&EgldOrEsdtTokenIdentifier::egld(), 0)
+    }
+
+    /// Everything the contract owes out: owner, beneficiary and commission balances.
+    fn total_claimable(&self) -> BigUint {
+        self.claimable_owner().get()
+            + self.total_beneficiary_claimable().get()
+            + self.total_commission_claimable().get()
+    }
+
+    fn total_liabilities(&self) -> BigUint {
+        self.total_bonds().get() + self.total_claimable() + self.insurance_pool().get()
+    }
+
+    fn require_solvent_after_payout(&self, amount: &BigUint) {
+        // Paying a claimable balance lowers assets and liabilities by the same
+        // amount, so this only passes if the contract is solvent to begin with.
+        let balance = self.get_egld_balance();
+        let liabilities = self.total_liabilities();
+        require!(
+            balance >= *amount && balance - amount >= liabilities - amount,
+            "Withdrawal would breach solvency"
+        );
+    }
+
+    /// Credits revenue net of its insurance share and returns that share.
+    fn credit_revenue(&self, category: RevenueCategory, amount: &BigUint) -> BigUint {
+        let insured = self.fund_insurance_pool(&category, amount);
+        let amount = &(amount - &insured);
+        self.lifetime_revenue(&category).update(|v| *v += amount);
+
+        let split = self.revenue_split().get();
+        if split.is_empty() {
+            self.claimable_owner().update(|v| *v += amount);
+            self.claimable_by_category(&category)
+                .update(|v| *v += amount);
+            return insured;
+        }
+
+        // Rounding dust goes to the last beneficiary so the credit is fully allocated.
+        let mut left = amount.clone();
+        let last_index = split.len() - 1;
+        for (index, share) in split.iter().enumerate() {
+            let part = if index == last_index {
+                left.clone()
+            } else {
+                self.compute_bps_amount(amount, share.bps)
+            };
+            if part > 0u64 {
+                self.beneficiary_claimable(&share.beneficiary)
+                    .update(|v| *v += &part);
+                left -= &part;
+            }
+        }
+        self.total_beneficiary_claimable()
+            .update(|v| *v += amount);
+        insured
+    }
+
+    /// Takes up to `amount` of revenue credited under `category` back: from owner
+    /// claimable (that category's bucket first) or, under a revenue split, from each
+    /// beneficiary in proportion to its share. Returns what could be taken.
+    fn debit_revenue(&self, category: RevenueCategory, amount: &BigUint) -> BigUint {
+        if *amount == 0u64 {
+            return BigUint::zero();
+        }
+
+        let split = self.revenue_split().get();
+        let taken = if split.is_empty() {
+            let taken = self.min_biguint(amount, &self.claimable_owner().get());
+            let bucket = self.claimable_by_category(&category).get();
+            let from_bucket = self.min_biguint(&taken, &bucket);
+            self.claimable_by_category(&category)
+                .set(&(bucket - &from_bucket));
+            self.claimable_owner().update(|v| *v -= &from_bucket);
+            let rest = &taken - &from_bucket;
+            if rest > 0u64 {
+                self.debit_claimable(&rest);
+            }
+            taken
+        } else {
+            let mut taken = BigUint::zero();
+            let mut allotted = BigUint::zero();
+            let last_index = split.len() - 1;
+            for (index, share) in split.iter().enumerate() {
+                let part = if index == last_index {
+                    amount - &allotted
+                } else {
+                    self.compute_bps_amount(amount, share.bps)
+                };
+                allotted += &part;
+                let available = self.beneficiary_claimable(&share.beneficiary).get();
+                let take = self.min_biguint(&part, &available);
+                if take > 0u64 {
+                    self.beneficiary_claimable(&share.beneficiary)
+                        .set(&(available - &take));
+                    taken += &take;
+                }
+            }
+            self.total_beneficiary_claimable()
+                .update(|v| *v -= &taken);
+            taken
+        };
+
+        let lifetime = self.lifetime_revenue(&category).get();
+        let reversed = self.min_biguint(&lifetime, &taken);
+        self.lifetime_revenue(&category).set(&(lifetime - &reversed));
+        taken
+    }
+
+    fn pay_insurance_claim(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+        claim: &mut InsuranceClaim<Self::Api>,
+    ) -> BigUint {
+        let limit = self.insurance_claim_limit().get();
+        let already_paid = self.agent_insurance_paid(agent).get();
+        let limit_left = if limit > already_paid {
+            limit - already_paid
+        } else {
+            BigUint::zero()
+        };
+        let unpaid = &claim.amount - &claim.paid;
+        let payout = self.min_biguint(
+            &self.min_biguint(&unpaid, &self.insurance_pool().get()),
+            &limit_left,
+        );
+        if payout == 0u64 {
+            return payout;
+        }
+
+        self.require_solvent_after_payout(&payout);
+        self.insurance_pool().update(|v| *v -= &payout);
+        self.insurance_paid_out().update(|v| *v += &payout);
+        self.agent_insurance_paid(agent)
+            .update(|v| *v += &payout);
+        claim.paid += &payout;
+        self.send().direct_egld(agent, &payout);
+
+        self.insurance_claim_paid_event(agent, epoch, &payout);
+        payout
     }
 
-    #[view(getClaimableOwner)]
-    fn get_claimable_owner_view(&self) -> BigUint {
-        self.claimable_owner().get()
+    /// Moves the insurance share of a collected fee or slash into the pool and
+    /// returns it.
+    fn fund_insurance_pool(&self, category: &RevenueCategory, amount: &BigUint) -> BigUint {
+        if *category != RevenueCategory::Settlement && *category != RevenueCategory::Slash {
+            return BigUint::zero();
+        }
+        let share = self.compute_bps_amount(amount, self.insurance_share_bps().get());
+        if share > 0u64 {
+            self.insurance_pool().update(|v| *v += &share);
+            self.insurance_funded().update(|v| *v += &share);
+        }
+        share
     }
 
-    #[view(getServiceStats)]
-    fn get_service_stats_view(
+    /// Protected value recorded at billing, or recomputed from the billed windows
+    /// for epochs billed before it was recorded.
+    fn epoch_protected_value_or_windows(&self, agent: &ManagedAddress, epoch: u64) -> BigUint {
+        if self.epoch_protected_value(agent, epoch).is_empty() {
+            return self.compute_protected_value(self.epoch_windows(agent, epoch).get());
+        }
+        self.epoch_protected_value(agent, epoch).get()
+    }
+
+    /// Late fees not yet added to `epoch_due`, and the epoch they would be accrued to.
+    fn pending_late_fee(&self, agent: &ManagedAddress, epoch: u64) -> (BigUint, u64) {
+        let current_epoch = self.blockchain().get_block_epoch();
+        if self.epoch_due(agent, epoch).is_empty() {
+            return (BigUint::zero(), current_epoch);
+        }
+
+        let accrued_to = if self.epoch_late_fee_accrued_to(agent, epoch).is_empty() {
+            self.epoch_deadline(agent, epoch).get()
+        } else {
+            self.epoch_late_fee_accrued_to(agent, epoch).get()
+        };
+        if current_epoch <= accrued_to {
+            return (BigUint::zero(), accrued_to);
+        }
+
+        // Cancel settles what the bond covers; whatever is left stops accruing.
+        if self.agent_info(agent).get().status == AgentStatus::Cancelled {
+            return (BigUint::zero(), current_epoch);
+        }
+
+        // Late fees are charged on the unpaid principal only, never compounded.
+        let principal =
+            self.epoch_due(agent, epoch).get() - self.epoch_late_fee_due(agent, epoch).get();
+        let mut base = principal;
+        base *= self.late_fee_rate_epochs_bps(accrued_to, current_epoch);
+        base /= BPS_DENOMINATOR;
+        (base, current_epoch)
+    }
+
+    /// Books debt recovered on cancel against the open epochs and then the payment plan,
+    /// late fees first, so their balances keep matching `outstanding_total`. Late fees
+    /// are credited as `LateFee`, the rest as `CancelDebtRecovery`.
+    fn apply_recovered_debt(&self, agent: &ManagedAddress, amount: &BigUint) {
+        let late_fees = self.apply_recovered_debt_to_balances(agent, amount);
+        if late_fees > 0u64 {
+            self.credit_revenue(RevenueCategory::LateFee, &late_fees);
+        }
+        let recovered = amount - &late_fees;
+        if recovered > 0u64 {
+            self.credit_revenue(RevenueCategory::CancelDebtRecovery, &recovered);
+        }
+    }
+
+    /// Returns the part of `amount` that paid off late fees.
+    fn apply_recovered_debt_to_balances(
         &self,
-    ) -> MultiValue7<u64, u64, u64, u64, u64, BigUint, BigUint> {
-        (
-            self.total_registered_agents().get(),
-            self.active_agent_count().get(),
-            self.promo_used().get(),
-            self.promo_free_slots().get(),
-            self.total_billed_windows().get(),
-            self.total_fees_billed().get(),
-            self.total_protected_value().get(),
-        )
-            .into()
+        agent: &ManagedAddress,
+        amount: &BigUint,
+    ) -> BigUint {
+        let mut left = amount.clone();
+        let mut late_fees = BigUint::zero();
+        let mut cleared = ManagedVec::<Self::Api, u64>::new();
+        for epoch in self.open_epochs(agent).iter() {
+            if left == 0u64 {
+                break;
+            }
+            let due = self.epoch_due(agent, epoch).get();
+            let take = self.min_biguint(&due, &left);
+            let late_fee_due = self.epoch_late_fee_due(agent, epoch).get();
+            let late_fee_taken = self.min_biguint(&take, &late_fee_due);
+            self.epoch_late_fee_due(agent, epoch)
+                .set(&(late_fee_due - &late_fee_taken));
+            self.epoch_due(agent, epoch).set(&(&due - &take));
+            if take == due {
+                cleared.push(epoch);
+            }
+            let billed_fee_taken = &take - &late_fee_taken;
+            if billed_fee_taken > 0u64 {
+                self.epoch_fee_collections(agent, epoch).push(&FeeCollection {
+                    category: RevenueCategory::CancelDebtRecovery,
+                    operator: self.operator().get(),
+                    commission: BigUint::zero(),
+                    insurance: BigUint::zero(),
+                    revenue: billed_fee_taken,
+                });
+            }
+            late_fees += late_fee_taken;
+            left -= &take;
+        }
+        for epoch in cleared.iter() {
+            self.open_epochs(agent).swap_remove(&epoch);
+        }
+
+        if left == 0u64 || self.payment_plan(agent).is_empty() {
+            return late_fees;
+        }
+        let mut plan = self.payment_plan(agent).get();
+        if plan.status != PaymentPlanStatus::Current
+            && plan.status != PaymentPlanStatus::Defaulted
+        {
+            return late_fees;
+        }
+        let take = self.min_biguint(&plan.remaining(), &left);
+        let late_fee_due = self.payment_plan_late_fee(agent).get();
+        let late_fee_taken = self.min_biguint(&take, &late_fee_due);
+        self.payment_plan_late_fee(agent)
+            .set(&(late_fee_due - &late_fee_taken));
+        plan.paid += &take;
+        if plan.remaining() == 0u64 {
+            plan.status = PaymentPlanStatus::Completed;
+        }
+        self.payment_plan(agent).set(&plan);
+        late_fees + late_fee_taken
     }
 
-    #[view(getAgentServiceStats)]
-    fn get_agent_service_stats_view(
+    /// Sum over the epochs in `(from, to]` of the late fee rate in force, in bps.
+    fn late_fee_rate_epochs_bps(&self, from: u64, to: u64) -> u64 {
+        let history = self.late_fee_rate_history();
+        if history.is_empty() {
+            return self.late_fee_bps_per_epoch().get() * (to - from);
+        }
+
+        let mut total = 0u64;
+        let mut period_end = to;
+        let mut index = history.len();
+        while index > 0 && period_end > from {
+            let period = history.get(index);
+            let period_start = core::cmp::max(period.from_epoch, from);
+            if period_end > period_start {
+                total += period.bps_per_epoch * (period_end - period_start);
+            }
+            period_end = period_start;
+            index -= 1;
+        }
+        total
+    }
+
+    fn accrue_late_fee(&self, agent: &ManagedAddress, epoch: u64) {
+        let (fee, accrued_to) = self.pending_late_fee(agent, epoch);
+        if self.epoch_due(agent, epoch).is_empty() {
+            return;
+        }
+
+        // Advanced even when nothing is charged, so a later rate never reaches back.
+        self.epoch_late_fee_accrued_to(agent, epoch).set(accrued_to);
+        if fee == 0u64 {
+            return;
+        }
+
+        self.epoch_due(agent, epoch).update(|v| *v += &fee);
+        self.epoch_late_fee_due(agent, epoch).update(|v| *v += &fee);
+        self.outstanding_total(agent).update(|v| *v += &fee);
+
+        self.late_fee_accrued_event(agent, epoch, &fee);
+    }
+
+    /// Scores an epoch whose due reached zero without enforcement, once: on time if
+    /// it cleared by the deadline, late otherwise.
+    fn record_settled_outcome(&self, agent: &ManagedAddress, epoch: u64, current_epoch: u64) {
+        if self.epoch_score_applied(agent, epoch).get() {
+            return;
+        }
+        if current_epoch <= self.epoch_deadline(agent, epoch).get() {
+            self.apply_credit_delta(agent, SCORE_BONUS_ON_TIME as i64);
+            self.record_probation_outcome(agent, true);
+            self.record_streak_outcome(agent, true);
+            self.epoch_state(agent, epoch).set(EpochState::SettledOnTime);
+        } else {
+            self.apply_credit_delta(agent, -(SCORE_PENALTY_LATE as i64));
+            self.record_probation_outcome(agent, false);
+            self.record_streak_outcome(agent, false);
+            self.epoch_state(agent, epoch).set(EpochState::SettledLate);
+        }
+        self.epoch_score_applied(agent, epoch).set(true);
+    }
+
+    /// Books a payment against an epoch: accrued late fees are paid off first, the
+    /// remainder is the billed fee itself.
+    fn collect_epoch_payment(
         &self,
-        agent: ManagedAddress,
-    ) -> MultiValue3<u64, BigUint, BigUint> {
-        if self.agent_info(&agent).is_empty() {
-            return (0u64, BigUint::zero(), BigUint::zero()).into();
+        agent: &ManagedAddress,
+        epoch: u64,
+        category: RevenueCategory,
+        amount: &BigUint,
+    ) {
+        let late_fee_due = self.epoch_late_fee_due(agent, epoch).get();
+        let late_fee_paid = self.min_biguint(amount, &late_fee_due);
+        if late_fee_paid > 0u64 {
+            self.epoch_late_fee_due(agent, epoch)
+                .set(&(late_fee_due - &late_fee_paid));
+            self.credit_revenue(RevenueCategory::LateFee, &late_fee_paid);
+        }
+
+        let billed_fee_paid = amount - &late_fee_paid;
+        if billed_fee_paid > 0u64 {
+            let collection = self.collect_billed_fee(category, &billed_fee_paid);
+            self.epoch_fee_collections(agent, epoch).push(&collection);
         }
-        (
-            self.agent_total_billed_windows(&agent).get(),
-            self.agent_total_fees_billed(&agent).get(),
-            self.agent_total_protected_value(&agent).get(),
-        )
-            .into()
     }
 
-    #[view(getConfig)]
-    fn get_config_view(
+    /// Books a payment against the payment plan: rolled-in late fees are paid off
+    /// first, the remainder goes through the billed fee split.
+    fn collect_plan_payment(
         &self,
-    ) -> MultiValue9<ManagedAddress, ManagedAddress, BigUint, BigUint, BigUint, u64, u64, u64, u64>
-    {
-        (
-            self.owner().get(),
-            self.operator().get(),
-            self.window_reward().get(),
-            self.setup_fee().get(),
-            self.min_bond().get(),
-            self.promo_free_slots().get(),
-            self.grace_epochs().get(),
-            self.max_backbill_epochs().get(),
-            self.hard_max_windows_per_epoch().get(),
-        )
-            .into()
+        agent: &ManagedAddress,
+        category: RevenueCategory,
+        amount: &BigUint,
+    ) {
+        let late_fee_due = self.payment_plan_late_fee(agent).get();
+        let late_fee_paid = self.min_biguint(amount, &late_fee_due);
+        if late_fee_paid > 0u64 {
+            self.payment_plan_late_fee(agent)
+                .set(&(late_fee_due - &late_fee_paid));
+            self.credit_revenue(RevenueCategory::LateFee, &late_fee_paid);
+        }
+
+        let billed_fee_paid = amount - &late_fee_paid;
+        if billed_fee_paid > 0u64 {
+            self.collect_billed_fee(category, &billed_fee_paid);
+        }
     }
 
-    #[view(getPromoUsage)]
-    fn get_promo_usage_view(&self) -> MultiValue2<u64, u64> {
-        (self.promo_used().get(), self.promo_free_slots().get()).into()
+    /// Splits a collected billed fee between the current operator's commission
+    /// and owner revenue, and returns where it went.
+    fn collect_billed_fee(
+        &self,
+        category: RevenueCategory,
+        amount: &BigUint,
+    ) -> FeeCollection<Self::Api> {
+        let operator = self.operator().get();
+        let commission = self.compute_bps_amount(amount, self.operator_commission_bps().get());
+        if commission > 0u64 {
+            self.commission_claimable(&operator)
+                .update(|v| *v += &commission);
+            self.commission_earned(&operator)
+                .update(|v| *v += &commission);
+            self.total_commission_claimable()
+                .update(|v| *v += &commission);
+            self.commission_accrued_event(&operator, &commission);
+        }
+
+        let mut revenue = amount - &commission;
+        let mut insurance = BigUint::zero();
+        if revenue > 0u64 {
+            insurance = self.credit_revenue(category.clone(), &revenue);
+            revenue -= &insurance;
+        }
+        FeeCollection {
+            category,
+            operator,
+            commission,
+            insurance,
+            revenue,
+        }
     }
 
-    #[view(getActiveAgentCount)]
-    fn get_active_agent_count_view(&self) -> u64 {
-        self.active_agent_count().get()
+    /// Credits `missed_windows` worth of the epoch's billed fee: unpaid principal is
+    /// reduced first, then the part rolled into a payment plan, and only then is what was
+    /// actually collected for the epoch refunded to bond. Credit on a fee that was never
+    /// collected is not paid out.
+    fn apply_service_credit(&self, agent: &ManagedAddress, epoch: u64, missed_windows: u64) {
+        require!(!self.epoch_due(agent, epoch).is_empty(), "Epoch not billed");
+        require!(!self.epoch_billed_fee(agent, epoch).is_empty(), "Billed fee not recorded");
+        require!(missed_windows > 0, "Invalid missed windows");
+        require!(
+            self.epoch_state(agent, epoch).get() != EpochState::WrittenOff,
+            "Epoch written off"
+        );
+
+        let windows = self.epoch_windows(agent, epoch).get();
+        let missed_total = self.epoch_missed_windows(agent, epoch).get() + missed_windows;
+        require!(missed_total <= windows, "Missed windows exceed billed windows");
+
+        let mut credit = self.epoch_billed_fee(agent, epoch).get();
+        credit *= missed_windows;
+        credit /= windows;
+
+        self.accrue_late_fee(agent, epoch);
+        let due = self.epoch_due(agent, epoch).get();
+        let principal = &due - &self.epoch_late_fee_due(agent, epoch).get();
+        let credited_to_due = self.min_biguint(&credit, &principal);
+        if credited_to_due > 0u64 {
+            self.epoch_due(agent, epoch).set(&(&due - &credited_to_due));
+            self.outstanding_total(agent)
+                .update(|v| *v -= &credited_to_due);
+            if due == credited_to_due {
+                self.open_epochs(agent).swap_remove(&epoch);
+                let current_epoch = self.blockchain().get_block_epoch();
+                self.record_settled_outcome(agent, epoch, current_epoch);
+                if self.agent_info(agent).get().status != AgentStatus::Cancelled
+                    && self.can_be_active(agent)
+                {
+                    self.set_status(agent, AgentStatus::Active);
+                }
+            }
+        }
+
+        let credited_to_plan =
+            self.credit_plan_principal(agent, epoch, &(&credit - &credited_to_due));
+
+        let operator = if self.epoch_operator(agent, epoch).is_empty() {
+            self.operator().get()
+        } else {
+            self.epoch_operator(agent, epoch).get()
+        };
+
+        // Fees already collected are refunded out of the buckets they were booked to;
+        // whatever those no longer hold stays owed to the agent.
+        let not_credited = &credit - &credited_to_due - &credited_to_plan;
+        let (refundable, refund) = self.reverse_collected_fee(agent, epoch, &not_credited);
+        if refund > 0u64 {
+            self.increase_bond(agent, &refund);
+        }
+        let owed = &refundable - &refund;
+        if owed > 0u64 {
+            self.agent_service_credit_owed(agent)
+                .update(|v| *v += &owed);
+            self.service_credit_owed_event(agent, epoch, &owed);
+        }
+
+        self.epoch_missed_windows(agent, epoch).set(missed_total);
+        self.epoch_service_credit(agent, epoch)
+            .update(|v| *v += &credit);
+        self.operator_missed_windows(&operator)
+            .update(|value| *value += missed_windows);
+        self.operator_service_credits(&operator)
+            .update(|v| *v += &credit);
+
+        self.service_credit_applied_event(
+            agent,
+            epoch,
+            &operator,
+            &ServiceCredit {
+                missed_windows,
+                credited_to_debt: credited_to_due + credited_to_plan,
+                refunded_to_bond: refund,
+            },
+        );
     }
 
-    fn compute_fee_amount(&self, windows: u64, fee_bps: u64) -> BigUint {
-        let mut fee = self.window_reward().get();
-        fee *= windows;
-        fee *= fee_bps;
-        fee /= BPS_DENOMINATOR;
-        require!(fee > 0u64, "Fee rounds to zero");
-        fee
+    /// Reduces the payment plan by up to `amount` of the epoch's principal rolled into
+    /// it, while the plan is still being paid. Returns the amount credited.
+    fn credit_plan_principal(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+        amount: &BigUint,
+    ) -> BigUint {
+        let in_plan = self.epoch_plan_principal(agent, epoch).get();
+        if *amount == 0u64 || in_plan == 0u64 || self.payment_plan(agent).is_empty() {
+            return BigUint::zero();
+        }
+        let mut plan = self.payment_plan(agent).get();
+        if plan.status != PaymentPlanStatus::Current
+            && plan.status != PaymentPlanStatus::Defaulted
+        {
+            return BigUint::zero();
+        }
+
+        let plan_principal = plan.remaining() - self.payment_plan_late_fee(agent).get();
+        let credited = self.min_biguint(&self.min_biguint(amount, &in_plan), &plan_principal);
+        if credited == 0u64 {
+            return credited;
+        }
+
+        self.epoch_plan_principal(agent, epoch)
+            .set(&(in_plan - &credited));
+        plan.total_debt -= &credited;
+        if plan.remaining() == 0u64 {
+            plan.status = PaymentPlanStatus::Completed;
+        }
+        self.payment_plan(agent).set(&plan);
+        self.outstanding_total(agent)
+            .update(|v| *v -= &credited);
+        credited
     }
 
-    fn compute_protected_value(&self, windows: u64) -> BigUint {
-        let mut value = self.window_reward().get();
-        value *= windows;
-        value
+    /// Takes back up to `amount` of what was collected for the epoch's billed fee, latest
+    /// collection first, from the exact commission, insurance and revenue category each
+    /// part was booked to, as far as those balances still hold it. Returns the amount
+    /// charged against the collections and the amount actually taken back.
+    fn reverse_collected_fee(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+        amount: &BigUint,
+    ) -> (BigUint, BigUint) {
+        let mut left = amount.clone();
+        let mut taken = BigUint::zero();
+        let mut collections = self.epoch_fee_collections(agent, epoch);
+        let mut index = collections.len();
+        while index > 0 && left > 0u64 {
+            let mut collection = collections.get(index);
+            index -= 1;
+            let total = collection.total();
+            if total == 0u64 {
+                continue;
+            }
+
+            let part = self.min_biguint(&left, &total);
+            let commission_part = &collection.commission * &part / &total;
+            let insurance_part = &collection.insurance * &part / &total;
+            let revenue_part = &part - &commission_part - &insurance_part;
+            left -= &part;
+
+            let commission = self.min_biguint(
+                &commission_part,
+                &self.commission_claimable(&collection.operator).get(),
+            );
+            if commission > 0u64 {
+                self.commission_claimable(&collection.operator)
+                    .update(|v| *v -= &commission);
+                self.commission_earned(&collection.operator)
+                    .update(|v| *v -= &commission);
+                self.total_commission_claimable()
+                    .update(|v| *v -= &commission);
+            }
+
+            let insurance = self.min_biguint(&insurance_part, &self.insurance_pool().get());
+            if insurance > 0u64 {
+                self.insurance_pool().update(|v| *v -= &insurance);
+                self.insurance_funded().update(|v| *v -= &insurance);
+            }
+
+            let revenue = self.debit_revenue(collection.category.clone(), &revenue_part);
+            taken += commission + insurance + revenue;
+
+            collection.commission -= commission_part;
+            collection.insurance -= insurance_part;
+            collection.revenue -= revenue_part;
+            collections.set(index + 1, &collection);
+        }
+        (amount - &left, taken)
     }
 
-    fn compute_bps_amount(&self, amount: &BigUint, bps: u64) -> BigUint {
-        if bps == 0 || amount == &BigUint::zero() {
-            return BigUint::zero();
+    /// Refunds as much of the agent's owed service credit as settlement revenue now
+    /// covers; the rest stays owed.
+    fn pay_owed_service_credit(&self, agent: &ManagedAddress) {
+        let owed = self.agent_service_credit_owed(agent).get();
+        if owed == 0u64 {
+            return;
+        }
+
+        let refund = self.debit_revenue(RevenueCategory::Settlement, &owed);
+        if refund == 0u64 {
+            return;
+        }
+
+        self.agent_service_credit_owed(agent)
+            .set(&(owed - &refund));
+        self.increase_bond(agent, &refund);
+
+        self.service_credit_paid_event(agent, &refund);
+    }
+
+    fn debit_claimable(&self, amount: &BigUint) {
+        self.claimable_owner().update(|v| *v -= amount);
+
+        // Drain category buckets in a fixed order. Revenue credited before the
+        // buckets existed is not tracked per category, so the drain may stop short.
+        let mut left = amount.clone();
+        for category in RevenueCategory::all() {
+            if left == 0u64 {
+                break;
+            }
+            let bucket = self.claimable_by_category(&category).get();
+            let take = self.min_biguint(&bucket, &left);
+            if take > 0u64 {
+                self.claimable_by_category(&category).set(&(bucket - &take));
+                left -= &take;
+            }
         }
-        let mut value = amount.clone();
-        value *= bps;
-        value /= BPS_DENOMINATOR;
-        value
     }
 
     fn apply_credit_delta(&self, agent: &ManagedAddress, delta: i64) {
@@ -637,6 +2847,52 @@ pub trait StreamAgencyEscrow {
             info.credit_score = info.credit_score.saturating_sub(sub);
         }
         self.agent_info(agent).set(&info);
+        self.refresh_agent_tier(agent);
+        self.update_leaderboard(agent, info.credit_score);
+    }
+
+    /// Re-ranks `agent` in the top-N board, keeping it sorted by score (descending);
+    /// among equal scores the agent that reached it first stays ahead.
+    fn update_leaderboard(&self, agent: &ManagedAddress, credit_score: u64) {
+        let board = self.leaderboard().get();
+        let mut next = ManagedVec::<Self::Api, LeaderboardEntry<Self::Api>>::new();
+        let mut inserted = false;
+        for entry in board.iter() {
+            if &entry.agent == agent {
+                continue;
+            }
+            if !inserted && credit_score > entry.credit_score {
+                next.push(LeaderboardEntry {
+                    agent: agent.clone(),
+                    credit_score,
+                });
+                inserted = true;
+            }
+            next.push(entry);
+        }
+        if !inserted {
+            next.push(LeaderboardEntry {
+                agent: agent.clone(),
+                credit_score,
+            });
+        }
+        if next.len() > LEADERBOARD_SIZE {
+            next = next.slice(0, LEADERBOARD_SIZE).unwrap_or_default();
+        }
+        self.leaderboard().set(&next);
+    }
+
+    fn remove_from_leaderboard(&self, agent: &ManagedAddress) {
+        let board = self.leaderboard().get();
+        let mut next = ManagedVec::<Self::Api, LeaderboardEntry<Self::Api>>::new();
+        for entry in board.iter() {
+            if &entry.agent != agent {
+                next.push(entry);
+            }
+        }
+        if next.len() != board.len() {
+            self.leaderboard().set(&next);
+        }
     }
 
     fn record_probation_outcome(&self, agent: &ManagedAddress, on_time: bool) {
@@ -658,6 +2914,8 @@ pub trait StreamAgencyEscrow {
                 if info.credit_score < MIN_ACTIVE_CREDIT {
                     info.credit_score = MIN_ACTIVE_CREDIT;
                     self.agent_info(agent).set(&info);
+                    self.refresh_agent_tier(agent);
+                    self.update_leaderboard(agent, info.credit_score);
                 }
             }
         } else {
@@ -665,6 +2923,61 @@ pub trait StreamAgencyEscrow {
         }
     }
 
+    fn record_streak_outcome(&self, agent: &ManagedAddress, on_time: bool) {
+        if !on_time {
+            self.agent_on_time_streak(agent).set(0u64);
+            self.agent_streak_fees(agent).clear();
+            return;
+        }
+
+        let streak = self.agent_on_time_streak(agent).get() + 1u64;
+        let required = self.streak_rebate_epochs().get();
+        if required == 0 || streak < required {
+            self.agent_on_time_streak(agent).set(streak);
+        } else {
+            // The earned rebate is owed until it can be paid, so the streak can restart.
+            let streak_fees = self.agent_streak_fees(agent).get();
+            let rebate = self.compute_bps_amount(&streak_fees, self.streak_rebate_bps().get());
+            self.agent_rebate_owed(agent).update(|v| *v += &rebate);
+            self.agent_on_time_streak(agent).set(0u64);
+            self.agent_streak_fees(agent).clear();
+        }
+
+        self.pay_owed_rebate(agent, streak);
+    }
+
+    /// Pays as much of the agent's owed rebate as the per-epoch cap and the settlement
+    /// revenue it is funded from allow; the rest stays owed for a later on-time epoch.
+    fn pay_owed_rebate(&self, agent: &ManagedAddress, streak: u64) {
+        let owed = self.agent_rebate_owed(agent).get();
+        if owed == 0u64 {
+            return;
+        }
+
+        let current_epoch = self.blockchain().get_block_epoch();
+        let cap = self.streak_rebate_cap_per_epoch().get();
+        let paid_this_epoch = self.rebates_paid_in_epoch(current_epoch).get();
+        let cap_left = if cap > paid_this_epoch {
+            cap - paid_this_epoch
+        } else {
+            BigUint::zero()
+        };
+        let rebate = self.debit_revenue(
+            RevenueCategory::Settlement,
+            &self.min_biguint(&owed, &cap_left),
+        );
+        if rebate == 0u64 {
+            return;
+        }
+
+        self.agent_rebate_owed(agent).set(&(owed - &rebate));
+        self.increase_bond(agent, &rebate);
+        self.rebates_paid_in_epoch(current_epoch)
+            .update(|v| *v += &rebate);
+
+        self.streak_rebate_paid_event(agent, streak, &rebate);
+    }
+
     fn is_probation_graduated(&self, agent: &ManagedAddress) -> bool {
         // Legacy agents do not have this mapper populated. Treat them as graduated.
         if self.agent_probation_graduated(agent).is_empty() {
@@ -677,23 +2990,226 @@ pub trait StreamAgencyEscrow {
         if self.agent_info(agent).is_empty() {
             return false;
         }
+        self.health_report(agent).healthy
+    }
+
+    fn health_report(&self, agent: &ManagedAddress) -> HealthReport<Self::Api> {
         let info = self.agent_info(agent).get();
-        if info.status == AgentStatus::Cancelled {
-            return false;
+        let not_cancelled = info.status != AgentStatus::Cancelled;
+
+        let probation_graduated = self.is_probation_graduated(agent);
+        let probation_on_time_remaining = if probation_graduated {
+            0u64
+        } else {
+            PROBATION_ON_TIME_EPOCHS_REQUIRED
+                .saturating_sub(self.agent_probation_on_time(agent).get())
+        };
+
+        let credit_ok = info.credit_score >= MIN_ACTIVE_CREDIT;
+
+        let bond = self.bond_balance(agent).get();
+        let required_bond = self.required_bond(agent);
+        let bond_ok = bond >= required_bond;
+        let bond_shortfall = if bond_ok {
+            BigUint::zero()
+        } else {
+            &required_bond - &bond
+        };
+
+        // Unscheduled debt must fit the credit limit, and none of it may be overdue.
+        let outstanding = self.outstanding_total(agent).get();
+        let unscheduled_debt = self.unscheduled_debt(agent);
+        let credit_limit = self.credit_limit(agent);
+        let allowed_debt = &outstanding - &unscheduled_debt + &credit_limit;
+        let overdue_debt = self.overdue_debt(agent);
+        let over_limit = if unscheduled_debt > credit_limit {
+            &unscheduled_debt - &credit_limit
+        } else {
+            BigUint::zero()
+        };
+        let debt_to_pay = if over_limit > overdue_debt {
+            over_limit
+        } else {
+            overdue_debt.clone()
+        };
+        let debt_ok = debt_to_pay == 0u64;
+
+        HealthReport {
+            not_cancelled,
+            probation_graduated,
+            probation_on_time_remaining,
+            credit_ok,
+            credit_score: info.credit_score,
+            min_active_credit: MIN_ACTIVE_CREDIT,
+            bond_ok,
+            bond,
+            required_bond,
+            bond_shortfall,
+            debt_ok,
+            outstanding,
+            allowed_debt,
+            overdue_debt,
+            debt_to_pay,
+            healthy: not_cancelled && probation_graduated && credit_ok && bond_ok && debt_ok,
+        }
+    }
+
+    /// Outstanding debt allowed by the agent's credit score. Zero without a curve.
+    fn credit_limit(&self, agent: &ManagedAddress) -> BigUint {
+        let score = self.agent_info(agent).get().credit_score;
+        find_score_band(&self.credit_limit_curve().get(), score)
+            .map(|band| band.limit)
+            .unwrap_or_default()
+    }
+
+    /// `min_bond` scaled by the agent's score band multiplier, if one applies.
+    fn required_bond(&self, agent: &ManagedAddress) -> BigUint {
+        let min_bond = self.min_bond().get();
+        let score = self.agent_info(agent).get().credit_score;
+        match find_score_band(&self.bond_multiplier_schedule().get(), score) {
+            Some(band) => self.compute_bps_amount(&min_bond, band.value),
+            None => min_bond,
         }
+    }
+
+    /// Base grace, plus the score band bonus once the agent has left probation.
+    fn effective_grace_epochs(&self, agent: &ManagedAddress) -> u64 {
+        let base = self.grace_epochs().get();
         if !self.is_probation_graduated(agent) {
-            return false;
+            return base;
+        }
+        let score = self.agent_info(agent).get().credit_score;
+        let bonus = find_score_band(&self.grace_bonus_schedule().get(), score)
+            .map(|band| band.value)
+            .unwrap_or(0u64);
+        let tier_bonus = self
+            .current_tier(agent)
+            .map(|tier| tier.grace_bonus_epochs)
+            .unwrap_or(0u64);
+        // Score band and tier bonuses stack, but never past the bonus cap.
+        let total_bonus = core::cmp::min(bonus.saturating_add(tier_bonus), MAX_GRACE_BONUS_EPOCHS);
+        base.saturating_add(total_bonus)
+    }
+
+    /// Tier with the highest `min_score` not above the agent's credit score.
+    fn current_tier(&self, agent: &ManagedAddress) -> Option<AgentTier<Self::Api>> {
+        let score = self.agent_info(agent).get().credit_score;
+        find_score_band(&self.agent_tiers().get(), score)
+    }
+
+    fn refresh_agent_tier(&self, agent: &ManagedAddress) {
+        let next = self
+            .current_tier(agent)
+            .map(|tier| tier.name.clone())
+            .unwrap_or_default();
+        let previous = self.agent_tier_name(agent).get();
+        if previous == next {
+            return;
+        }
+
+        self.agent_tier_name(agent).set(&next);
+        let credit_score = self.agent_info(agent).get().credit_score;
+        self.tier_changed_event(agent, &previous, &next, credit_score);
+    }
+
+    fn build_score_bands(
+        &self,
+        bands: MultiValueEncoded<MultiValue2<u64, u64>>,
+    ) -> ManagedVec<ScoreBand> {
+        let mut result = ManagedVec::new();
+        let mut previous_min_score: Option<u64> = None;
+        for entry in bands.into_iter() {
+            let (min_score, value) = entry.into_tuple();
+            self.require_next_band_score(&mut previous_min_score, min_score);
+            result.push(ScoreBand { min_score, value });
+        }
+        result
+    }
+
+    /// Score bands must be strictly increasing and within the credit range.
+    fn require_next_band_score(&self, previous_min_score: &mut Option<u64>, min_score: u64) {
+        require!(min_score <= MAX_CREDIT, "Invalid band score");
+        if let Some(previous) = *previous_min_score {
+            require!(min_score > previous, "Bands must be sorted by score");
+        }
+        *previous_min_score = Some(min_score);
+    }
+
+    /// Debt of open epochs past their deadline, including late fees not yet accrued,
+    /// plus what is left of a payment plan that defaulted or missed an installment.
+    fn overdue_debt(&self, agent: &ManagedAddress) -> BigUint {
+        let current_epoch = self.blockchain().get_block_epoch();
+        let mut overdue = BigUint::zero();
+        for epoch in self.open_epochs(agent).iter() {
+            if current_epoch > self.epoch_deadline(agent, epoch).get() {
+                overdue += self.epoch_due(agent, epoch).get();
+                overdue += self.pending_late_fee(agent, epoch).0;
+            }
+        }
+
+        if !self.payment_plan(agent).is_empty() {
+            let plan = self.payment_plan(agent).get();
+            let missed = match plan.status {
+                PaymentPlanStatus::Defaulted => true,
+                PaymentPlanStatus::Current => current_epoch > plan.next_due_epoch,
+                _ => false,
+            };
+            if missed {
+                overdue += plan.remaining();
+            }
+        }
+        overdue
+    }
+
+    /// Outstanding debt not covered by a current payment plan.
+    fn unscheduled_debt(&self, agent: &ManagedAddress) -> BigUint {
+        let outstanding = self.outstanding_total(agent).get();
+        let scheduled = self.scheduled_plan_debt(agent);
+        if outstanding > scheduled {
+            outstanding - scheduled
+        } else {
+            BigUint::zero()
         }
-        if info.credit_score < MIN_ACTIVE_CREDIT {
-            return false;
+    }
+
+    fn write_off_epoch(&self, agent: &ManagedAddress, epoch: u64) -> BigUint {
+        let due = self.epoch_due(agent, epoch).get();
+        if due == 0u64 {
+            return due;
         }
-        if self.bond_balance(agent).get() < self.min_bond().get() {
-            return false;
+
+        self.epoch_due(agent, epoch).set(BigUint::zero());
+        self.epoch_late_fee_due(agent, epoch).clear();
+        self.epoch_state(agent, epoch).set(EpochState::WrittenOff);
+        self.epoch_score_applied(agent, epoch).set(true);
+        self.open_epochs(agent).swap_remove(&epoch);
+
+        let outstanding = self.outstanding_total(agent).get();
+        let reduced = self.min_biguint(&due, &outstanding);
+        self.outstanding_total(agent).set(&(outstanding - &reduced));
+        self.record_written_off(agent, &due);
+
+        self.epoch_written_off_event(agent, epoch, &due);
+        due
+    }
+
+    fn record_written_off(&self, agent: &ManagedAddress, amount: &BigUint) {
+        self.agent_total_written_off(agent)
+            .update(|v| *v += amount);
+        self.total_written_off().update(|v| *v += amount);
+    }
+
+    /// Debt covered by a payment plan that is current, which does not block activity.
+    fn scheduled_plan_debt(&self, agent: &ManagedAddress) -> BigUint {
+        if self.payment_plan(agent).is_empty() {
+            return BigUint::zero();
         }
-        if self.outstanding_total(agent).get() > 0u64 {
-            return false;
+        let plan = self.payment_plan(agent).get();
+        let current_epoch = self.blockchain().get_block_epoch();
+        if plan.status != PaymentPlanStatus::Current || current_epoch > plan.next_due_epoch {
+            return BigUint::zero();
         }
-        true
+        plan.remaining()
     }
 
     fn set_status(&self, agent: &ManagedAddress, next: AgentStatus) {
@@ -713,6 +3229,19 @@ pub trait StreamAgencyEscrow {
         if next == AgentStatus::Active {
             self.active_agent_count().update(|count| *count += 1u64);
         }
+        if prev == AgentStatus::Active || next == AgentStatus::Active {
+            self.record_active_agents_snapshot();
+        }
+        if prev != AgentStatus::Active {
+            self.status_count(&prev)
+                .update(|count| *count = count.saturating_sub(1));
+        }
+        if next != AgentStatus::Active {
+            self.status_count(&next).update(|count| *count += 1u64);
+        }
+        if next == AgentStatus::Cancelled {
+            self.remove_from_leaderboard(agent);
+        }
 
         info.status = next.clone();
         self.agent_info(agent).set(&info);
@@ -720,6 +3249,75 @@ pub trait StreamAgencyEscrow {
         self.status_changed_event(agent, self.status_to_code(&next), info.credit_score);
     }
 
+    /// Agents at list positions `cursor..cursor + max_agents` (0-based, at most
+    /// `MAX_AGENTS_PER_PAGE`) and the cursor of the next page, 0 after the last one.
+    fn agent_page(
+        &self,
+        cursor: usize,
+        max_agents: usize,
+    ) -> (ManagedVec<ManagedAddress>, usize) {
+        require!(max_agents > 0, "Invalid page size");
+        let agents = self.agents();
+        let total = agents.len();
+        let end = core::cmp::min(
+            cursor.saturating_add(core::cmp::min(max_agents, MAX_AGENTS_PER_PAGE)),
+            total,
+        );
+
+        let mut page = ManagedVec::new();
+        for index in cursor..end {
+            page.push(agents.get(index + 1));
+        }
+        let next_cursor = if end < total { end } else { 0 };
+        (page, next_cursor)
+    }
+
+    fn add_aged_debt(
+        &self,
+        health: &mut PortfolioHealth<Self::Api>,
+        epochs_past_deadline: u64,
+        amount: &BigUint,
+    ) {
+        match epochs_past_deadline {
+            0 => health.outstanding_current += amount,
+            1..=2 => health.outstanding_1_to_2_epochs += amount,
+            3..=5 => health.outstanding_3_to_5_epochs += amount,
+            _ => health.outstanding_over_5_epochs += amount,
+        }
+    }
+
+    fn record_active_agents_snapshot(&self) {
+        let current_epoch = self.blockchain().get_block_epoch();
+        if self.active_agents_at(current_epoch).is_empty() {
+            self.active_agents_snapshot_epochs().push(&current_epoch);
+        }
+        self.active_agents_at(current_epoch)
+            .set(self.active_agent_count().get());
+    }
+
+    /// Active agent count at the end of `epoch`, taken from the latest snapshot at or
+    /// before it. Zero before the first snapshot.
+    fn active_agents_in_epoch(&self, epoch: u64) -> u64 {
+        let snapshots = self.active_agents_snapshot_epochs();
+        // VecMapper is 1-based; find the last snapshot epoch <= `epoch`.
+        let mut low = 1usize;
+        let mut high = snapshots.len();
+        let mut found = 0usize;
+        while low <= high {
+            let mid = (low + high) / 2;
+            if snapshots.get(mid) <= epoch {
+                found = mid;
+                low = mid + 1;
+            } else {
+                high = mid - 1;
+            }
+        }
+        if found == 0 {
+            return 0;
+        }
+        self.active_agents_at(snapshots.get(found)).get()
+    }
+
     fn status_to_code(&self, status: &AgentStatus) -> u64 {
         match status {
             AgentStatus::Active => STATUS_ACTIVE,
@@ -729,6 +3327,12 @@ pub trait StreamAgencyEscrow {
         }
     }
 
+    fn bump_config_version(&self) -> u64 {
+        let next = self.config_version().get() + 1u64;
+        self.config_version().set(next);
+        next
+    }
+
     fn min_biguint(&self, a: &BigUint, b: &BigUint) -> BigUint {
         if a <= b {
             a.clone()
@@ -746,6 +3350,30 @@ pub trait StreamAgencyEscrow {
         require!(caller == self.operator().get(), "Only operator");
     }
 
+    fn only_owner_or_operator(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.owner().get() || caller == self.operator().get(),
+            "Only owner or operator"
+        );
+    }
+
+    fn only_operator_or_arbiter(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.operator().get()
+                || (!self.arbiter().is_empty() && caller == self.arbiter().get()),
+            "Only operator or arbiter"
+        );
+    }
+
+    fn arbiter_or_zero(&self) -> ManagedAddress {
+        if self.arbiter().is_empty() {
+            return ManagedAddress::zero();
+        }
+        self.arbiter().get()
+    }
+
     fn only_owner(&self) {
         let caller = self.blockchain().get_caller();
         require!(caller == self.owner().get(), "Only owner");
@@ -769,6 +3397,7 @@ pub trait StreamAgencyEscrow {
         #[indexed] agent: &ManagedAddress,
         #[indexed] epoch: u64,
         #[indexed] windows: u64,
+        #[indexed] grace_epochs: u64,
         due: &BigUint,
     );
 
@@ -788,6 +3417,42 @@ pub trait StreamAgencyEscrow {
         slashed: &BigUint,
     );
 
+    #[event("paymentPlanOffered")]
+    fn payment_plan_offered_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] installments: u64,
+        #[indexed] interval_epochs: u64,
+        total_debt: &BigUint,
+    );
+
+    #[event("installmentPaid")]
+    fn installment_paid_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] installments_paid: u64,
+        paid: &BigUint,
+    );
+
+    #[event("installmentEnforced")]
+    fn installment_enforced_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] installments_paid: u64,
+        slashed: &BigUint,
+    );
+
+    #[event("epochWrittenOff")]
+    fn epoch_written_off_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        amount: &BigUint,
+    );
+
+    #[event("debtWrittenOff")]
+    fn debt_written_off_event(&self, #[indexed] agent: &ManagedAddress, amount: &BigUint);
+
     #[event("statusChanged")]
     fn status_changed_event(
         &self,
@@ -802,6 +3467,235 @@ pub trait StreamAgencyEscrow {
     #[event("ownerWithdrawn")]
     fn owner_withdrawn_event(&self, #[indexed] to: &ManagedAddress, amount: &BigUint);
 
+    #[event("beneficiaryClaimed")]
+    fn beneficiary_claimed_event(&self, #[indexed] beneficiary: &ManagedAddress, amount: &BigUint);
+
+    #[event("commissionAccrued")]
+    fn commission_accrued_event(&self, #[indexed] operator: &ManagedAddress, amount: &BigUint);
+
+    #[event("commissionClaimed")]
+    fn commission_claimed_event(&self, #[indexed] operator: &ManagedAddress, amount: &BigUint);
+
+    #[event("operatorCommissionChanged")]
+    fn operator_commission_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &OperatorCommissionChange,
+    );
+
+    #[event("lateFeeAccrued")]
+    fn late_fee_accrued_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        amount: &BigUint,
+    );
+
+    #[event("lateFeeRateChanged")]
+    fn late_fee_rate_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &LateFeeRateChange,
+    );
+
+    #[event("creditLimitCurveChanged")]
+    fn credit_limit_curve_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &CreditLimitCurveChange<Self::Api>,
+    );
+
+    #[event("bondMultiplierScheduleChanged")]
+    fn bond_multiplier_schedule_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &BondMultiplierScheduleChange<Self::Api>,
+    );
+
+    #[event("graceBonusScheduleChanged")]
+    fn grace_bonus_schedule_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &GraceBonusScheduleChange<Self::Api>,
+    );
+
+    #[event("agentTiersChanged")]
+    fn agent_tiers_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &AgentTiersChange<Self::Api>,
+    );
+
+    #[event("tierChanged")]
+    fn tier_changed_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] old_tier: &ManagedBuffer,
+        #[indexed] new_tier: &ManagedBuffer,
+        credit_score: u64,
+    );
+
+    #[event("streakRebatePaid")]
+    fn streak_rebate_paid_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] streak: u64,
+        rebate: &BigUint,
+    );
+
+    #[event("streakRebateChanged")]
+    fn streak_rebate_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &StreakRebateChange<Self::Api>,
+    );
+
+    #[event("priceCurveChanged")]
+    fn price_curve_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &PriceCurveChange<Self::Api>,
+    );
+
+    #[event("revenueSplitChanged")]
+    fn revenue_split_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &RevenueSplitChange<Self::Api>,
+    );
+
+    #[event("solvencyChecked")]
+    fn solvency_checked_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        check: &SolvencyCheck<Self::Api>,
+    );
+
+    #[event("insuranceClaimFiled")]
+    fn insurance_claim_filed_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        amount: &BigUint,
+    );
+
+    #[event("insuranceClaimResolved")]
+    fn insurance_claim_resolved_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        #[indexed] approved: bool,
+        paid: &BigUint,
+    );
+
+    #[event("insuranceClaimPaid")]
+    fn insurance_claim_paid_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        amount: &BigUint,
+    );
+
+    #[event("insuranceConfigChanged")]
+    fn insurance_config_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &InsuranceConfigChange<Self::Api>,
+    );
+
+    #[event("arbiterChanged")]
+    fn arbiter_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &ArbiterChange<Self::Api>,
+    );
+
+    #[event("serviceCreditApplied")]
+    fn service_credit_applied_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        #[indexed] operator: &ManagedAddress,
+        credit: &ServiceCredit<Self::Api>,
+    );
+
+    #[event("serviceCreditOwed")]
+    fn service_credit_owed_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        owed: &BigUint,
+    );
+
+    #[event("serviceCreditPaid")]
+    fn service_credit_paid_event(&self, #[indexed] agent: &ManagedAddress, amount: &BigUint);
+
+    #[event("challengeFiled")]
+    fn challenge_filed_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        missed_windows: u64,
+    );
+
+    #[event("challengeResolved")]
+    fn challenge_resolved_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        #[indexed] epoch: u64,
+        #[indexed] accepted: bool,
+        missed_windows: u64,
+    );
+
+    #[event("operatorChanged")]
+    fn operator_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &OperatorChange<Self::Api>,
+    );
+
+    #[event("ownerChanged")]
+    fn owner_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &OwnerChange<Self::Api>,
+    );
+
+    #[event("windowRewardChanged")]
+    fn window_reward_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &WindowRewardChange<Self::Api>,
+    );
+
+    #[event("promoSlotsChanged")]
+    fn promo_slots_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &PromoSlotsChange,
+    );
+
+    #[event("maxBackbillEpochsChanged")]
+    fn max_backbill_epochs_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &MaxBackbillEpochsChange,
+    );
+
+    #[event("hardMaxWindowsChanged")]
+    fn hard_max_windows_changed_event(
+        &self,
+        #[indexed] caller: &ManagedAddress,
+        change: &HardMaxWindowsChange,
+    );
+
+    #[event("billingGuardsChanged")]
+    fn billing_guards_changed_event(
+        &self,
+        #[indexed] agent: &ManagedAddress,
+        change: &BillingGuardsChange<Self::Api>,
+    );
+
     #[storage_mapper("owner")]
     fn owner(&self) -> SingleValueMapper<ManagedAddress>;
 
@@ -835,6 +3729,105 @@ pub trait StreamAgencyEscrow {
     #[storage_mapper("claimableOwner")]
     fn claimable_owner(&self) -> SingleValueMapper<BigUint>;
 
+    #[storage_mapper("claimableByCategory")]
+    fn claimable_by_category(&self, category: &RevenueCategory) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lifetimeRevenue")]
+    fn lifetime_revenue(&self, category: &RevenueCategory) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("lateFeeBpsPerEpoch")]
+    fn late_fee_bps_per_epoch(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("lateFeeRateHistory")]
+    fn late_fee_rate_history(&self) -> VecMapper<LateFeeRatePeriod>;
+
+    #[storage_mapper("creditLimitCurve")]
+    fn credit_limit_curve(&self) -> SingleValueMapper<ManagedVec<CreditLimitBand<Self::Api>>>;
+
+    #[storage_mapper("bondMultiplierSchedule")]
+    fn bond_multiplier_schedule(&self) -> SingleValueMapper<ManagedVec<ScoreBand>>;
+
+    #[storage_mapper("graceBonusSchedule")]
+    fn grace_bonus_schedule(&self) -> SingleValueMapper<ManagedVec<ScoreBand>>;
+
+    #[storage_mapper("agentTiers")]
+    fn agent_tiers(&self) -> SingleValueMapper<ManagedVec<AgentTier<Self::Api>>>;
+
+    #[storage_mapper("streakRebateEpochs")]
+    fn streak_rebate_epochs(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("streakRebateBps")]
+    fn streak_rebate_bps(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("streakRebateCapPerEpoch")]
+    fn streak_rebate_cap_per_epoch(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("rebatesPaidInEpoch")]
+    fn rebates_paid_in_epoch(&self, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("priceCurve")]
+    fn price_curve(&self) -> SingleValueMapper<ManagedVec<PriceBreakpoint>>;
+
+    #[storage_mapper("revenueSplit")]
+    fn revenue_split(&self) -> SingleValueMapper<ManagedVec<RevenueShare<Self::Api>>>;
+
+    #[storage_mapper("beneficiaries")]
+    fn beneficiaries(&self) -> UnorderedSetMapper<ManagedAddress>;
+
+    #[storage_mapper("beneficiaryClaimable")]
+    fn beneficiary_claimable(&self, beneficiary: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("totalBeneficiaryClaimable")]
+    fn total_beneficiary_claimable(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("operatorCommissionBps")]
+    fn operator_commission_bps(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("commissionClaimable")]
+    fn commission_claimable(&self, operator: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("commissionEarned")]
+    fn commission_earned(&self, operator: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("totalCommissionClaimable")]
+    fn total_commission_claimable(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("arbiter")]
+    fn arbiter(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[storage_mapper("insuranceShareBps")]
+    fn insurance_share_bps(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("insuranceClaimLimit")]
+    fn insurance_claim_limit(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("insurancePool")]
+    fn insurance_pool(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("insuranceFunded")]
+    fn insurance_funded(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("insurancePaidOut")]
+    fn insurance_paid_out(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("operatorBilledWindows")]
+    fn operator_billed_windows(&self, operator: &ManagedAddress) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("operatorMissedWindows")]
+    fn operator_missed_windows(&self, operator: &ManagedAddress) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("operatorServiceCredits")]
+    fn operator_service_credits(&self, operator: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("configVersion")]
+    fn config_version(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("totalBonds")]
+    fn total_bonds(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("statusCount")]
+    fn status_count(&self, status: &AgentStatus) -> SingleValueMapper<u64>;
+
     #[storage_mapper("activeAgentCount")]
     fn active_agent_count(&self) -> SingleValueMapper<u64>;
 
@@ -850,6 +3843,36 @@ pub trait StreamAgencyEscrow {
     #[storage_mapper("totalProtectedValue")]
     fn total_protected_value(&self) -> SingleValueMapper<BigUint>;
 
+    #[storage_mapper("totalWrittenOff")]
+    fn total_written_off(&self) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochWindowsBilled")]
+    fn epoch_windows_billed(&self, epoch: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("epochFeesBilled")]
+    fn epoch_fees_billed(&self, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochCollectedOnTime")]
+    fn epoch_collected_on_time(&self, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochCollectedLate")]
+    fn epoch_collected_late(&self, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochCollectedBySlash")]
+    fn epoch_collected_by_slash(&self, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("activeAgentsAt")]
+    fn active_agents_at(&self, epoch: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("activeAgentsSnapshotEpochs")]
+    fn active_agents_snapshot_epochs(&self) -> VecMapper<u64>;
+
+    #[storage_mapper("leaderboard")]
+    fn leaderboard(&self) -> SingleValueMapper<ManagedVec<LeaderboardEntry<Self::Api>>>;
+
+    #[storage_mapper("agents")]
+    fn agents(&self) -> VecMapper<ManagedAddress>;
+
     #[storage_mapper("agentInfo")]
     fn agent_info(&self, agent: &ManagedAddress) -> SingleValueMapper<AgentInfo<Self::Api>>;
 
@@ -859,6 +3882,30 @@ pub trait StreamAgencyEscrow {
     #[storage_mapper("outstandingTotal")]
     fn outstanding_total(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
 
+    #[storage_mapper("agentOpenEpochs")]
+    fn open_epochs(&self, agent: &ManagedAddress) -> UnorderedSetMapper<u64>;
+
+    #[storage_mapper("agentPaymentPlan")]
+    fn payment_plan(&self, agent: &ManagedAddress) -> SingleValueMapper<PaymentPlan<Self::Api>>;
+
+    #[storage_mapper("agentInsurancePaid")]
+    fn agent_insurance_paid(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("agentPaymentPlanLateFee")]
+    fn payment_plan_late_fee(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("agentTierName")]
+    fn agent_tier_name(&self, agent: &ManagedAddress) -> SingleValueMapper<ManagedBuffer>;
+
+    #[storage_mapper("agentOnTimeStreak")]
+    fn agent_on_time_streak(&self, agent: &ManagedAddress) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("agentRebateOwed")]
+    fn agent_rebate_owed(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("agentStreakFees")]
+    fn agent_streak_fees(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
     #[storage_mapper("agentProbationOnTime")]
     fn agent_probation_on_time(&self, agent: &ManagedAddress) -> SingleValueMapper<u64>;
 
@@ -874,9 +3921,54 @@ pub trait StreamAgencyEscrow {
     #[storage_mapper("agentTotalProtectedValue")]
     fn agent_total_protected_value(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
 
+    #[storage_mapper("agentTotalWrittenOff")]
+    fn agent_total_written_off(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
     #[storage_mapper("epochWindows")]
     fn epoch_windows(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<u64>;
 
+    #[storage_mapper("epochProtectedValue")]
+    fn epoch_protected_value(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochBilledFee")]
+    fn epoch_billed_fee(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochOperator")]
+    fn epoch_operator(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<ManagedAddress>;
+
+    #[storage_mapper("epochMissedWindows")]
+    fn epoch_missed_windows(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("epochServiceCredit")]
+    fn epoch_service_credit(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochFeeCollections")]
+    fn epoch_fee_collections(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+    ) -> VecMapper<FeeCollection<Self::Api>>;
+
+    #[storage_mapper("epochPlanPrincipal")]
+    fn epoch_plan_principal(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("agentServiceCreditOwed")]
+    fn agent_service_credit_owed(&self, agent: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("serviceChallenge")]
+    fn service_challenge(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+    ) -> SingleValueMapper<ServiceChallenge>;
+
+    #[storage_mapper("insuranceClaim")]
+    fn insurance_claim(
+        &self,
+        agent: &ManagedAddress,
+        epoch: u64,
+    ) -> SingleValueMapper<InsuranceClaim<Self::Api>>;
+
     #[storage_mapper("epochDue")]
     fn epoch_due(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
 
@@ -888,4 +3980,10 @@ pub trait StreamAgencyEscrow {
 
     #[storage_mapper("epochState")]
     fn epoch_state(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<EpochState>;
+
+    #[storage_mapper("epochLateFeeDue")]
+    fn epoch_late_fee_due(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<BigUint>;
+
+    #[storage_mapper("epochLateFeeAccruedTo")]
+    fn epoch_late_fee_accrued_to(&self, agent: &ManagedAddress, epoch: u64) -> SingleValueMapper<u64>;
 }
@@ -1,6 +1,8 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+use core::borrow::Borrow;
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
 pub enum AgentStatus {
@@ -19,6 +21,7 @@ pub enum EpochState {
     SettledLate,
     Slashed,
     Delinquent,
+    WrittenOff,
 }
 
 #[type_abi]
@@ -34,3 +37,595 @@ pub struct AgentInfo<M: ManagedTypeApi> {
     pub last_billed_epoch: u64,
     pub metadata: ManagedBuffer<M>,
 }
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
+pub enum RevenueCategory {
+    SetupFee,
+    Settlement,
+    Slash,
+    CancelDebtRecovery,
+    ChurnPenalty,
+    LateFee,
+}
+
+impl RevenueCategory {
+    /// All categories, in the order owner withdrawals drain them.
+    pub fn all() -> [RevenueCategory; 6] {
+        [
+            RevenueCategory::SetupFee,
+            RevenueCategory::Settlement,
+            RevenueCategory::Slash,
+            RevenueCategory::CancelDebtRecovery,
+            RevenueCategory::ChurnPenalty,
+            RevenueCategory::LateFee,
+        ]
+    }
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct RevenueShare<M: ManagedTypeApi> {
+    pub beneficiary: ManagedAddress<M>,
+    pub bps: u64,
+}
+
+/// Data of a service credit event: debt reduced (epoch due and plan) and refund paid.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct ServiceCredit<M: ManagedTypeApi> {
+    pub missed_windows: u64,
+    pub credited_to_debt: BigUint<M>,
+    pub refunded_to_bond: BigUint<M>,
+}
+
+/// Where a collected part of an epoch's billed fee went, so a service credit can take
+/// exactly that back.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct FeeCollection<M: ManagedTypeApi> {
+    pub category: RevenueCategory,
+    pub operator: ManagedAddress<M>,
+    pub commission: BigUint<M>,
+    pub insurance: BigUint<M>,
+    pub revenue: BigUint<M>,
+}
+
+impl<M: ManagedTypeApi> FeeCollection<M> {
+    pub fn total(&self) -> BigUint<M> {
+        &self.commission + &self.insurance + &self.revenue
+    }
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
+pub enum PaymentPlanStatus {
+    Current,
+    Defaulted,
+    Completed,
+    WrittenOff,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct PaymentPlan<M: ManagedTypeApi> {
+    pub total_debt: BigUint<M>,
+    pub paid: BigUint<M>,
+    pub installment_amount: BigUint<M>,
+    pub installments_total: u64,
+    pub installments_paid: u64,
+    pub interval_epochs: u64,
+    pub next_due_epoch: u64,
+    pub max_windows_per_epoch: u64,
+    pub status: PaymentPlanStatus,
+}
+
+impl<M: ManagedTypeApi> PaymentPlan<M> {
+    pub fn remaining(&self) -> BigUint<M> {
+        &self.total_debt - &self.paid
+    }
+
+    /// Amount the next payment must cover. The last installment takes the rounding
+    /// remainder, and a defaulted plan is due in full.
+    pub fn next_installment_due(&self) -> BigUint<M> {
+        let remaining = self.remaining();
+        if self.status == PaymentPlanStatus::Defaulted
+            || self.installments_paid + 1 >= self.installments_total
+            || remaining < self.installment_amount
+        {
+            return remaining;
+        }
+        self.installment_amount.clone()
+    }
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct CreditLimitBand<M: ManagedTypeApi> {
+    pub min_score: u64,
+    pub limit: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct ScoreBand {
+    pub min_score: u64,
+    pub value: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct AgentTier<M: ManagedTypeApi> {
+    pub name: ManagedBuffer<M>,
+    pub min_score: u64,
+    pub max_windows_per_epoch: u64,
+    pub fee_discount_bps: u64,
+    pub grace_bonus_epochs: u64,
+}
+
+/// Entry of an owner-configured table keyed by the lowest credit score it applies to.
+pub trait ScoreBanded {
+    fn min_score(&self) -> u64;
+}
+
+impl<M: ManagedTypeApi> ScoreBanded for CreditLimitBand<M> {
+    fn min_score(&self) -> u64 {
+        self.min_score
+    }
+}
+
+impl ScoreBanded for ScoreBand {
+    fn min_score(&self) -> u64 {
+        self.min_score
+    }
+}
+
+impl<M: ManagedTypeApi> ScoreBanded for AgentTier<M> {
+    fn min_score(&self) -> u64 {
+        self.min_score
+    }
+}
+
+/// The band with the highest `min_score` not above `score`, in bands sorted by score.
+pub fn find_score_band<M, T>(bands: &ManagedVec<M, T>, score: u64) -> Option<T>
+where
+    M: ManagedTypeApi,
+    T: ManagedVecItem + ScoreBanded + Clone,
+{
+    let mut found = None;
+    for band in bands.iter() {
+        let band: &T = band.borrow();
+        if band.min_score() > score {
+            break;
+        }
+        found = Some(band.clone());
+    }
+    found
+}
+
+/// Late fee rate in force from `from_epoch` until the next period starts.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct LateFeeRatePeriod {
+    pub from_epoch: u64,
+    pub bps_per_epoch: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct PriceBreakpoint {
+    pub from_window: u64,
+    pub multiplier_bps: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
+pub enum BillRejection {
+    AgentNotEnrolled,
+    ZeroWindows,
+    EpochNotClosed,
+    EpochTooOld,
+    AgentCancelled,
+    BeforeJoinEpoch,
+    OutOfBillingOrder,
+    ExceedsAgentWindows,
+    ExceedsProbationWindows,
+    ExceedsHardCap,
+    ExceedsTierWindows,
+    ExceedsPaymentPlanWindows,
+    AlreadyBilled,
+    FeeRoundsToZero,
+    ExceedsMaxCharge,
+    ExceedsCreditLimit,
+}
+
+impl BillRejection {
+    /// Error message `billEpoch` fails with for this rejection.
+    pub fn message(&self) -> &'static str {
+        match self {
+            BillRejection::AgentNotEnrolled => "Agent not enrolled",
+            BillRejection::ZeroWindows => "Windows must be positive",
+            BillRejection::EpochNotClosed => "Epoch not closed yet",
+            BillRejection::EpochTooOld => "Epoch too old to bill",
+            BillRejection::AgentCancelled => "Agent cancelled",
+            BillRejection::BeforeJoinEpoch => "Cannot bill before join epoch",
+            BillRejection::OutOfBillingOrder => "Epoch already passed in billing order",
+            BillRejection::ExceedsAgentWindows => "Exceeds max windows per epoch",
+            BillRejection::ExceedsProbationWindows => "Exceeds probation windows cap",
+            BillRejection::ExceedsHardCap => "Exceeds global windows hard cap",
+            BillRejection::ExceedsTierWindows => "Exceeds tier windows cap",
+            BillRejection::ExceedsPaymentPlanWindows => "Exceeds payment plan windows cap",
+            BillRejection::AlreadyBilled => "Epoch already billed",
+            BillRejection::FeeRoundsToZero => "Fee rounds to zero",
+            BillRejection::ExceedsMaxCharge => "Exceeds agent max charge per epoch",
+            BillRejection::ExceedsCreditLimit => "Exceeds agent credit limit",
+        }
+    }
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct BillQuote<M: ManagedTypeApi> {
+    pub due: BigUint<M>,
+    pub protected_value: BigUint<M>,
+    pub grace_epochs: u64,
+    pub deadline: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub enum BillPreview<M: ManagedTypeApi> {
+    Accepted(BillQuote<M>),
+    Rejected(BillRejection),
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct HealthReport<M: ManagedTypeApi> {
+    pub not_cancelled: bool,
+    pub probation_graduated: bool,
+    pub probation_on_time_remaining: u64,
+    pub credit_ok: bool,
+    pub credit_score: u64,
+    pub min_active_credit: u64,
+    pub bond_ok: bool,
+    pub bond: BigUint<M>,
+    pub required_bond: BigUint<M>,
+    pub bond_shortfall: BigUint<M>,
+    pub debt_ok: bool,
+    pub outstanding: BigUint<M>,
+    pub allowed_debt: BigUint<M>,
+    pub overdue_debt: BigUint<M>,
+    pub debt_to_pay: BigUint<M>,
+    pub healthy: bool,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct CancelPreview<M: ManagedTypeApi> {
+    pub bond: BigUint<M>,
+    pub debt_recovered: BigUint<M>,
+    pub debt_remaining: BigUint<M>,
+    pub churn_penalty: BigUint<M>,
+    pub penalty_free_from_epoch: u64,
+    pub payout: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct EnforceableEpoch<M: ManagedTypeApi> {
+    pub agent: ManagedAddress<M>,
+    pub epoch: u64,
+    pub due: BigUint<M>,
+    pub expected_slash: BigUint<M>,
+    pub outcome: EpochState,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct AgentDashboard<M: ManagedTypeApi> {
+    pub info: AgentInfo<M>,
+    pub tier: Option<AgentTier<M>>,
+    pub bond: BigUint<M>,
+    pub outstanding: BigUint<M>,
+    pub probation_on_time: u64,
+    pub probation_required: u64,
+    pub probation_graduated: bool,
+    pub total_billed_windows: u64,
+    pub total_fees_billed: BigUint<M>,
+    pub total_protected_value: BigUint<M>,
+    pub total_written_off: BigUint<M>,
+    pub can_be_active: bool,
+    pub churn_penalty_free_from_epoch: u64,
+    pub next_deadline: Option<u64>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct StreakRebateSettings<M: ManagedTypeApi> {
+    pub streak_epochs: u64,
+    pub rebate_bps: u64,
+    pub cap_per_epoch: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct InsuranceSettings<M: ManagedTypeApi> {
+    pub share_bps: u64,
+    pub claim_limit: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct BillingGuards<M: ManagedTypeApi> {
+    pub max_windows_per_epoch: u64,
+    pub max_charge_per_epoch: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct SolvencyCheck<M: ManagedTypeApi> {
+    pub balance: BigUint<M>,
+    pub liabilities: BigUint<M>,
+}
+
+// Data of config change events: the setting before and after, and the config
+// version the change bumped to.
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct OperatorCommissionChange {
+    pub old: u64,
+    pub new: u64,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct LateFeeRateChange {
+    pub old: u64,
+    pub new: u64,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct CreditLimitCurveChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, CreditLimitBand<M>>,
+    pub new: ManagedVec<M, CreditLimitBand<M>>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct BondMultiplierScheduleChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, ScoreBand>,
+    pub new: ManagedVec<M, ScoreBand>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct GraceBonusScheduleChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, ScoreBand>,
+    pub new: ManagedVec<M, ScoreBand>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct AgentTiersChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, AgentTier<M>>,
+    pub new: ManagedVec<M, AgentTier<M>>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct StreakRebateChange<M: ManagedTypeApi> {
+    pub old: StreakRebateSettings<M>,
+    pub new: StreakRebateSettings<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct PriceCurveChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, PriceBreakpoint>,
+    pub new: ManagedVec<M, PriceBreakpoint>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct RevenueSplitChange<M: ManagedTypeApi> {
+    pub old: ManagedVec<M, RevenueShare<M>>,
+    pub new: ManagedVec<M, RevenueShare<M>>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct InsuranceConfigChange<M: ManagedTypeApi> {
+    pub old: InsuranceSettings<M>,
+    pub new: InsuranceSettings<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct ArbiterChange<M: ManagedTypeApi> {
+    pub old: ManagedAddress<M>,
+    pub new: ManagedAddress<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct OperatorChange<M: ManagedTypeApi> {
+    pub old: ManagedAddress<M>,
+    pub new: ManagedAddress<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct OwnerChange<M: ManagedTypeApi> {
+    pub old: ManagedAddress<M>,
+    pub new: ManagedAddress<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct WindowRewardChange<M: ManagedTypeApi> {
+    pub old: BigUint<M>,
+    pub new: BigUint<M>,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct PromoSlotsChange {
+    pub old: u64,
+    pub new: u64,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct MaxBackbillEpochsChange {
+    pub old: u64,
+    pub new: u64,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct HardMaxWindowsChange {
+    pub old: u64,
+    pub new: u64,
+    pub config_version: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct BillingGuardsChange<M: ManagedTypeApi> {
+    pub old: BillingGuards<M>,
+    pub new: BillingGuards<M>,
+    pub config_version: u64,
+}
+
+/// Fields are only ever appended; `schema_version` is bumped whenever they are.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct EscrowConfig<M: ManagedTypeApi> {
+    pub schema_version: u32,
+    pub config_version: u64,
+    pub owner: ManagedAddress<M>,
+    pub operator: ManagedAddress<M>,
+    pub window_reward: BigUint<M>,
+    pub setup_fee: BigUint<M>,
+    pub min_bond: BigUint<M>,
+    pub promo_free_slots: u64,
+    pub grace_epochs: u64,
+    pub max_backbill_epochs: u64,
+    pub hard_max_windows_per_epoch: u64,
+    pub operator_commission_bps: u64,
+    pub late_fee_bps_per_epoch: u64,
+    pub streak_rebate_epochs: u64,
+    pub streak_rebate_bps: u64,
+    pub streak_rebate_cap_per_epoch: BigUint<M>,
+    pub insurance_share_bps: u64,
+    pub insurance_claim_limit: BigUint<M>,
+    pub arbiter: ManagedAddress<M>,
+}
+
+/// Fields are only ever appended; `schema_version` is bumped whenever they are.
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct ServiceStats<M: ManagedTypeApi> {
+    pub schema_version: u32,
+    pub total_registered_agents: u64,
+    pub active_agent_count: u64,
+    pub promo_used: u64,
+    pub promo_free_slots: u64,
+    pub total_billed_windows: u64,
+    pub total_fees_billed: BigUint<M>,
+    pub total_protected_value: BigUint<M>,
+    pub total_written_off: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct EpochMetrics<M: ManagedTypeApi> {
+    pub epoch: u64,
+    pub windows_billed: u64,
+    pub fees_billed: BigUint<M>,
+    pub collected_on_time: BigUint<M>,
+    pub collected_late: BigUint<M>,
+    pub collected_by_slash: BigUint<M>,
+    pub active_agents: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct PortfolioHealth<M: ManagedTypeApi> {
+    pub active_agents: u64,
+    pub paused_agents: u64,
+    pub suspended_agents: u64,
+    pub cancelled_agents: u64,
+    pub outstanding_current: BigUint<M>,
+    pub outstanding_1_to_2_epochs: BigUint<M>,
+    pub outstanding_3_to_5_epochs: BigUint<M>,
+    pub outstanding_over_5_epochs: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone)]
+pub struct LeaderboardEntry<M: ManagedTypeApi> {
+    pub agent: ManagedAddress<M>,
+    pub credit_score: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct TopAgent<M: ManagedTypeApi> {
+    pub agent: ManagedAddress<M>,
+    pub credit_score: u64,
+    pub metadata: ManagedBuffer<M>,
+    pub total_billed_windows: u64,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
+pub enum InsuranceClaimStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct InsuranceClaim<M: ManagedTypeApi> {
+    pub amount: BigUint<M>,
+    pub filed_epoch: u64,
+    pub status: InsuranceClaimStatus,
+    pub paid: BigUint<M>,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Eq)]
+pub enum ChallengeStatus {
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct ServiceChallenge {
+    pub missed_windows: u64,
+    pub filed_epoch: u64,
+    pub status: ChallengeStatus,
+}
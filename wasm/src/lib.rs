@@ -6,9 +6,9 @@
 
 // Init:                                 1
 // Upgrade:                              1
-// Endpoints:                           26
+// Endpoints:                           90
 // Async Callback (empty):               1
-// Total number of exported functions:  29
+// Total number of exported functions:  93
 
 #![no_std]
 
@@ -29,7 +29,32 @@ multiversx_sc_wasm_adapter::endpoints! {
         billEpoch => bill_epoch
         settleEpoch => settle_epoch
         enforceEpoch => enforce_epoch
+        offerPaymentPlan => offer_payment_plan
+        payInstallment => pay_installment
+        enforceInstallment => enforce_installment
+        writeOffDebt => write_off_debt
+        fileInsuranceClaim => file_insurance_claim
+        resolveInsuranceClaim => resolve_insurance_claim
+        payInsuranceClaim => pay_insurance_claim_endpoint
+        recordMissedWindows => record_missed_windows
+        challengeMissedWindows => challenge_missed_windows
+        resolveChallenge => resolve_challenge
         withdrawOwner => withdraw_owner
+        claim => claim
+        claimCommission => claim_commission
+        setOperatorCommission => set_operator_commission
+        setLateFeeRate => set_late_fee_rate
+        setCreditLimitCurve => set_credit_limit_curve
+        setBondMultiplierSchedule => set_bond_multiplier_schedule
+        setGraceBonusSchedule => set_grace_bonus_schedule
+        setAgentTiers => set_agent_tiers
+        refreshAgentTiers => refresh_agent_tiers
+        setStreakRebate => set_streak_rebate
+        setPriceCurve => set_price_curve
+        setRevenueSplit => set_revenue_split
+        setInsuranceConfig => set_insurance_config
+        setArbiter => set_arbiter
+        checkSolvency => check_solvency
         setOperator => set_operator
         setOwner => set_owner
         setWindowReward => set_window_reward
@@ -37,13 +62,52 @@ multiversx_sc_wasm_adapter::endpoints! {
         setMaxBackbillEpochs => set_max_backbill_epochs
         setHardMaxWindowsPerEpoch => set_hard_max_windows_per_epoch
         getAgentInfo => get_agent_info_view
+        getAgentDashboard => get_agent_dashboard_view
+        getAgentTier => get_agent_tier_view
+        getAgentTiers => get_agent_tiers_view
+        getStreak => get_streak_view
+        getStreakRebateOwed => get_streak_rebate_owed_view
+        getStreakRebateConfig => get_streak_rebate_config_view
+        getHealthReport => get_health_report_view
+        previewCancel => preview_cancel_view
         getAgentFinancials => get_agent_financials_view
+        getTopAgents => get_top_agents_view
+        getCreditLimit => get_credit_limit_view
+        getCreditLimitCurve => get_credit_limit_curve_view
+        getRequiredBond => get_required_bond_view
+        getBondMultiplierSchedule => get_bond_multiplier_schedule_view
+        getGraceEpochs => get_grace_epochs_view
+        getGraceBonusSchedule => get_grace_bonus_schedule_view
+        quoteFee => quote_fee_view
+        previewBill => preview_bill_view
+        getPriceCurve => get_price_curve_view
         getEpochDebt => get_epoch_debt_view
+        getEpochLateFee => get_epoch_late_fee_view
+        getPaymentPlan => get_payment_plan_view
         getEpochState => get_epoch_state_view
+        getEnforceable => get_enforceable_view
+        getEpochMetrics => get_epoch_metrics_view
+        getPortfolioHealth => get_portfolio_health_view
         getClaimableOwner => get_claimable_owner_view
+        getRevenueBreakdown => get_revenue_breakdown_view
+        getRevenueSplit => get_revenue_split_view
+        getBeneficiaryBalances => get_beneficiary_balances_view
+        getOperatorCommission => get_operator_commission_view
+        getInsurancePool => get_insurance_pool_view
+        getInsuranceConfig => get_insurance_config_view
+        getInsuranceClaim => get_insurance_claim_view
+        getAgentInsurancePaid => get_agent_insurance_paid_view
+        getServiceCredit => get_service_credit_view
+        getServiceCreditOwed => get_service_credit_owed_view
+        getServiceChallenge => get_service_challenge_view
+        getOperatorReliability => get_operator_reliability_view
+        getSolvency => get_solvency_view
         getServiceStats => get_service_stats_view
         getAgentServiceStats => get_agent_service_stats_view
+        getWrittenOff => get_written_off_view
         getConfig => get_config_view
+        getEscrowConfig => get_escrow_config_view
+        getEscrowStats => get_escrow_stats_view
         getPromoUsage => get_promo_usage_view
         getActiveAgentCount => get_active_agent_count_view
     )